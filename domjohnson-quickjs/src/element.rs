@@ -36,9 +36,40 @@ impl JsElement {
         Ok(())
     }
 
+    #[qjs(get)]
+    pub fn hidden(&self) -> bool {
+        let dom = self.dom.read().unwrap();
+        dom.get(self.id)
+            .and_then(|node| node.as_element())
+            .is_some_and(|el| el.hidden())
+    }
+
+    #[qjs(set, rename = "hidden")]
+    pub fn set_hidden(&self, hidden: bool) -> rquickjs::Result<()> {
+        let mut dom = self.dom.write().unwrap();
+        if let Some(el) = dom.get_mut(self.id).and_then(|node| node.as_element_mut()) {
+            el.set_hidden(hidden);
+        }
+        Ok(())
+    }
+
     #[qjs(get, rename = "innerHTML")]
     pub fn inner_html(&self) -> rquickjs::Result<String> {
         let dom = self.dom.read().unwrap();
         Ok(dom.inner_html(self.id))
     }
+
+    #[qjs(set, rename = "innerHTML")]
+    pub fn set_inner_html(&self, html: String) -> rquickjs::Result<()> {
+        let mut dom = self.dom.write().unwrap();
+        dom.set_inner_html(self.id, &html);
+        Ok(())
+    }
+
+    #[qjs(set, rename = "outerHTML")]
+    pub fn set_outer_html(&self, html: String) -> rquickjs::Result<()> {
+        let mut dom = self.dom.write().unwrap();
+        dom.set_outer_html(self.id, &html);
+        Ok(())
+    }
 }
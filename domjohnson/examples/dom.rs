@@ -1,3 +1,4 @@
+use domjohnson::builder::el;
 use domjohnson::Document;
 
 static HTML: &str = r#"
@@ -40,5 +41,12 @@ fn main() {
 
     dom.append(dom.select("head").get(0).unwrap(), title_tag);
 
+    let body = dom.select("body").get(0).unwrap();
+    el("main")
+        .class("content")
+        .child(el("h1").text("Hello"))
+        .child(el("ul").children(["one", "two"].into_iter().map(|item| el("li").text(item))))
+        .append_to(&mut dom, body);
+
     println!("{dom}");
 }
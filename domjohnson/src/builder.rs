@@ -0,0 +1,234 @@
+//! Fluent construction of subtrees.
+//!
+//! ```
+//! use domjohnson::builder::{el, text};
+//! use domjohnson::Document;
+//!
+//! let mut dom = Document::new_html5();
+//! let body = dom.select("body").get(0).unwrap();
+//!
+//! el("div")
+//!     .class("card")
+//!     .attr("id", "intro")
+//!     .child(el("h2").text("Hello"))
+//!     .child(el("p").child(text("Built without ")).child(el("em").text("HTML")))
+//!     .append_to(&mut dom, body);
+//! ```
+
+use generational_indextree::NodeId;
+use html5ever::{namespace_url, ns, LocalName, QualName};
+use smol_str::SmolStr;
+
+use crate::node::{Comment, Element, Node, Text};
+use crate::Document;
+
+/// Starts an HTML element.
+pub fn el(name: &str) -> ElementBuilder {
+    ElementBuilder::new(QualName::new(None, ns!(html), LocalName::from(name)))
+}
+
+/// A text node.
+pub fn text(text: impl Into<SmolStr>) -> NodeBuilder {
+    NodeBuilder::Node(Node::Text(Text { text: text.into() }))
+}
+
+/// A comment node.
+pub fn comment(comment: impl Into<SmolStr>) -> NodeBuilder {
+    NodeBuilder::Node(Node::Comment(Comment {
+        comment: comment.into(),
+    }))
+}
+
+/// An element with its attributes and children, inserted into a document
+/// with [`ElementBuilder::build`] or [`ElementBuilder::append_to`].
+#[derive(Debug, Clone)]
+pub struct ElementBuilder {
+    element: Element,
+    children: Vec<NodeBuilder>,
+}
+
+/// A child of an [`ElementBuilder`].
+#[derive(Debug, Clone)]
+pub enum NodeBuilder {
+    Element(ElementBuilder),
+    Node(Node),
+    /// A node already in the document, which is moved into place.
+    Existing(NodeId),
+}
+
+impl ElementBuilder {
+    /// Starts an element with any name, like an SVG element.
+    pub fn new(name: QualName) -> Self {
+        ElementBuilder {
+            element: Element::new(name, Vec::new()),
+            children: Vec::new(),
+        }
+    }
+
+    /// Sets an attribute.
+    pub fn attr(mut self, attr: &str, value: &str) -> Self {
+        self.element.set_attr(attr, value);
+        self
+    }
+
+    /// Sets a namespaced attribute, like `xlink:href`.
+    pub fn attr_ns(mut self, name: QualName, value: &str) -> Self {
+        self.element.set_attr_ns(name, value);
+        self
+    }
+
+    /// Sets a boolean attribute, or leaves it out if `value` is false.
+    pub fn bool_attr(mut self, attr: &str, value: bool) -> Self {
+        self.element.set_bool_attr(attr, value);
+        self
+    }
+
+    /// Sets the `id` attribute.
+    pub fn id(self, id: &str) -> Self {
+        self.attr("id", id)
+    }
+
+    /// Adds a class, which can be called repeatedly.
+    pub fn class(mut self, class: &str) -> Self {
+        self.element.append_class(class);
+        self
+    }
+
+    /// Appends a child.
+    pub fn child(mut self, child: impl Into<NodeBuilder>) -> Self {
+        self.children.push(child.into());
+        self
+    }
+
+    /// Appends children, for example from an iterator over rows.
+    pub fn children<I>(mut self, children: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<NodeBuilder>,
+    {
+        self.children.extend(children.into_iter().map(Into::into));
+        self
+    }
+
+    /// Appends a text child.
+    pub fn text(self, text: impl Into<SmolStr>) -> Self {
+        self.child(self::text(text))
+    }
+
+    /// Inserts the element and its descendants into the document as an
+    /// orphan, returning the element's id.
+    pub fn build(self, dom: &mut Document) -> NodeId {
+        NodeBuilder::Element(self).build(dom)
+    }
+
+    /// Inserts the element as the last child of `parent`, returning its id.
+    pub fn append_to(self, dom: &mut Document, parent: NodeId) -> NodeId {
+        let node = self.build(dom);
+        dom.append(parent, node);
+        node
+    }
+}
+
+impl NodeBuilder {
+    /// Inserts the node into the document, returning its id. Built nodes
+    /// are orphans, existing ones are returned as they are.
+    pub fn build(self, dom: &mut Document) -> NodeId {
+        match self {
+            NodeBuilder::Element(builder) => {
+                let node = dom.create_node(Node::Element(builder.element));
+                for child in builder.children {
+                    let child = child.build(dom);
+                    dom.append(node, child);
+                }
+                node
+            }
+            NodeBuilder::Node(node) => dom.create_node(node),
+            NodeBuilder::Existing(node) => node,
+        }
+    }
+}
+
+impl From<ElementBuilder> for NodeBuilder {
+    fn from(builder: ElementBuilder) -> Self {
+        NodeBuilder::Element(builder)
+    }
+}
+
+impl From<NodeId> for NodeBuilder {
+    fn from(node: NodeId) -> Self {
+        NodeBuilder::Existing(node)
+    }
+}
+
+impl From<&str> for NodeBuilder {
+    fn from(text: &str) -> Self {
+        self::text(text)
+    }
+}
+
+impl From<String> for NodeBuilder {
+    fn from(text: String) -> Self {
+        self::text(text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use html5ever::{namespace_url, ns, LocalName, QualName};
+
+    use super::{comment, el, text, ElementBuilder};
+    use crate::Document;
+
+    #[test]
+    fn builds_nested_elements() {
+        let mut dom = Document::new_html5();
+        let body = dom.select("body").get(0).unwrap();
+
+        let div = el("div")
+            .class("a")
+            .class("b c")
+            .child(el("h2").text("Hi & bye"))
+            .children(["x", "y"])
+            .child(comment("note"))
+            .append_to(&mut dom, body);
+
+        assert_eq!(
+            dom.node_ref(div).html(),
+            "<div class=\"a b c\"><h2>Hi &amp; bye</h2>xy<!--note--></div>"
+        );
+        assert_eq!(dom.select(".c > h2").len(), 1);
+    }
+
+    #[test]
+    fn attributes() {
+        let mut dom = Document::new_html5();
+        let input = el("input").bool_attr("disabled", true).build(&mut dom);
+        let hidden = el("input")
+            .bool_attr("disabled", false)
+            .id("x")
+            .build(&mut dom);
+        let svg = ElementBuilder::new(QualName::new(None, ns!(svg), LocalName::from("use")))
+            .attr_ns(
+                QualName::new(None, ns!(xlink), LocalName::from("href")),
+                "#a",
+            )
+            .build(&mut dom);
+
+        assert_eq!(dom.node_ref(input).html(), "<input disabled=\"\">");
+        assert_eq!(dom.node_ref(hidden).html(), "<input id=\"x\">");
+        assert_eq!(dom.node_ref(svg).html(), "<use xlink:href=\"#a\"></use>");
+    }
+
+    #[test]
+    fn built_nodes_are_orphans_and_existing_nodes_move() {
+        let mut dom = Document::parse("<p>moved</p>");
+        let p = dom.select("p").get(0).unwrap();
+
+        let orphan = text("t").build(&mut dom);
+        assert!(dom.orhpans().any(|node| node == orphan));
+
+        let div = el("div").child(p).build(&mut dom);
+        assert_eq!(dom.node_ref(div).html(), "<div><p>moved</p></div>");
+        assert_eq!(dom.select("body p").len(), 0);
+    }
+}
@@ -0,0 +1,135 @@
+use generational_indextree::{Arena, NodeId};
+use html5ever::tendril::TendrilSink;
+use html5ever::{local_name, namespace_url, ns, parse_fragment, ParseOpts, QualName};
+
+use crate::node::Node;
+
+use super::{build_subtree, collect_subtree, free_subtree, is_template, sink::DocumentBuilder};
+
+/// Replaces the children of `node` with `html` parsed in its context.
+pub(crate) fn set_inner_html(tree: &mut Arena<Node>, node: NodeId, html: &str) {
+    // The children of a template live in its contents fragment.
+    let target = if is_template(tree[node].get()) {
+        tree[node].first_child().unwrap_or(node)
+    } else {
+        node
+    };
+
+    let context = context_name(tree, node);
+    let nodes = parse_into(tree, context, html);
+
+    let children = target.children(tree).collect::<Vec<_>>();
+    for child in children {
+        free_subtree(tree, child);
+    }
+
+    for child in nodes {
+        target.append(child, tree);
+    }
+}
+
+/// Appends `html` parsed in the context of `node` to its children.
+#[cfg(feature = "markdown")]
+pub(crate) fn append_html(tree: &mut Arena<Node>, node: NodeId, html: &str) {
+    let context = context_name(tree, node);
+    for child in parse_into(tree, context, html) {
+        node.append(child, tree);
+    }
+}
+
+/// Replaces `node` with `html` parsed in the context of its parent, freeing
+/// `node`. Does nothing if `node` has no parent.
+pub(crate) fn set_outer_html(tree: &mut Arena<Node>, node: NodeId, html: &str) {
+    let Some(parent) = tree[node].parent() else {
+        return;
+    };
+
+    let context = context_name(tree, parent);
+    let nodes = parse_into(tree, context, html);
+
+    for child in nodes {
+        node.insert_before(child, tree);
+    }
+
+    free_subtree(tree, node);
+}
+
+/// The context element name used for fragment parsing. Nodes that aren't
+/// elements (document and fragment roots) are parsed as if inside `body`.
+fn context_name(tree: &Arena<Node>, node: NodeId) -> QualName {
+    match tree[node].get().as_element() {
+        Some(element) => element.name.clone(),
+        None => QualName::new(None, ns!(html), local_name!("body")),
+    }
+}
+
+/// Parses `html` as a fragment and copies the resulting nodes into `tree`
+/// as orphans.
+fn parse_into(tree: &mut Arena<Node>, context: QualName, html: &str) -> Vec<NodeId> {
+    let parser = parse_fragment(
+        DocumentBuilder::new(),
+        ParseOpts::default(),
+        context,
+        Vec::new(),
+    );
+    let fragment = parser.one(html);
+
+    // The fragment parser puts the parsed nodes inside an `html` root element.
+    let Some(root) = fragment.tree[fragment.root].first_child() else {
+        return Vec::new();
+    };
+
+    root.children(&fragment.tree)
+        .map(|child| build_subtree(tree, collect_subtree(&fragment.tree, child, true)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Document;
+
+    #[test]
+    fn set_inner_html_parses_in_context() {
+        let mut dom = Document::parse("<table><tbody><tr><td>a</td></tr></tbody></table>");
+        let tbody = dom.select("tbody").get(0).unwrap();
+
+        dom.set_inner_html(tbody, "<tr><td>b</td><td>c</td></tr>");
+        assert_eq!(dom.inner_html(tbody), "<tr><td>b</td><td>c</td></tr>");
+    }
+
+    #[test]
+    fn set_inner_html_fills_template_contents() {
+        let mut dom = Document::parse("<template></template>");
+        let template = dom.select("template").get(0).unwrap();
+
+        dom.set_inner_html(template, "<b>x</b>");
+        assert_eq!(dom.inner_html(template), "<b>x</b>");
+    }
+
+    #[test]
+    fn set_outer_html_replaces_the_node() {
+        let mut dom = Document::parse("<p>a</p><p id=x>b</p><p>c</p>");
+        let x = dom.select("#x").get(0).unwrap();
+        let body = dom.select("body").get(0).unwrap();
+
+        dom.set_outer_html(x, "<h2>b</h2> text");
+        assert_eq!(dom.inner_html(body), "<p>a</p><h2>b</h2> text<p>c</p>");
+        assert!(dom.get(x).is_none());
+    }
+
+    #[test]
+    fn replaced_nodes_are_freed() {
+        let mut dom = Document::parse("<div><p>a</p></div><p id=x>b</p>");
+        let div = dom.select("div").get(0).unwrap();
+        let count = dom.tree.count();
+
+        for _ in 0..100 {
+            dom.set_inner_html(div, "<p>a</p>");
+        }
+        let x = dom.select("#x").get(0).unwrap();
+        dom.set_outer_html(x, "<p id=x>b</p>");
+
+        assert_eq!(dom.orhpans().count(), 0);
+        assert_eq!(dom.tree.count(), count);
+    }
+}
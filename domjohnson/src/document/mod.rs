@@ -1,18 +1,34 @@
 use core::fmt;
+use std::io::{self, Read, Write};
 
 use self::sink::DocumentBuilder;
+use crate::element::canonical::ContentHasher;
+use crate::element::kind::preserves_whitespace;
+use crate::element::node_mut::NodeMut;
 use crate::element::node_ref::Text;
+use crate::error::SnapshotError;
+#[cfg(feature = "markdown")]
+use crate::markdown::append_markdown;
+use crate::markdown::{to_markdown, MarkdownOptions};
 use crate::node::{Comment, Doctype, Element, Node};
 use crate::selection::Selection;
-use crate::{MatchScope, Matcher, Matches, NodeRef};
-use generational_indextree::{Arena, NodeId};
+use crate::{MatchScope, Matcher, Matches, MinifyOptions, NodeRef, SerializeOptions};
+use generational_indextree::{Arena, NodeEdge, NodeId};
 use html5ever::serialize::TraversalScope;
 use html5ever::tendril::TendrilSink;
+use html5ever::{expanded_name, local_name, namespace_url, ns, LocalName, QualName};
 use html5ever::{interface::QuirksMode, parse_document, ParseOpts};
-use html5ever::{namespace_url, ns, LocalName, QualName};
 use smol_str::SmolStr;
 
+mod fragment;
+#[cfg(feature = "serde")]
+mod serde_impl;
 mod sink;
+mod snapshot;
+
+#[cfg(feature = "markdown")]
+pub(crate) use self::fragment::append_html;
+pub(crate) use self::fragment::{set_inner_html, set_outer_html};
 
 pub struct Document {
     quirks: QuirksMode,
@@ -26,6 +42,26 @@ impl Document {
         parser.one(html)
     }
 
+    /// Loads a document written by [`Document::to_snapshot`].
+    pub fn from_snapshot(bytes: &[u8]) -> Result<Document, SnapshotError> {
+        snapshot::read_snapshot(bytes)
+    }
+
+    /// Reads a snapshot to the end and loads it.
+    pub fn read_snapshot<R: Read>(reader: &mut R) -> Result<Document, SnapshotError> {
+        snapshot::read_snapshot_from(reader)
+    }
+
+    /// Builds an HTML5 document whose body holds `markdown` converted to
+    /// HTML, see [`Document::append_markdown`].
+    #[cfg(feature = "markdown")]
+    pub fn from_markdown(markdown: &str) -> Document {
+        let mut doc = Document::new_html5();
+        let body = doc.select("body").get(0).expect("body");
+        doc.append_markdown(body, markdown);
+        doc
+    }
+
     pub fn new_html5() -> Document {
         let mut tree = Arena::new();
 
@@ -38,26 +74,20 @@ impl Document {
 
         root.append(doctype, &mut tree);
 
-        let head_tag = tree.new_node(Node::Element(Element {
-            name: QualName::new(None, ns!(html), LocalName::from("head")),
-            id: None,
-            classes: Default::default(),
-            attrs: Default::default(),
-        }));
+        let head_tag = tree.new_node(Node::Element(Element::new(
+            QualName::new(None, ns!(html), LocalName::from("head")),
+            Vec::new(),
+        )));
 
-        let body_tag = tree.new_node(Node::Element(Element {
-            name: QualName::new(None, ns!(html), LocalName::from("body")),
-            id: None,
-            classes: Default::default(),
-            attrs: Default::default(),
-        }));
+        let body_tag = tree.new_node(Node::Element(Element::new(
+            QualName::new(None, ns!(html), LocalName::from("body")),
+            Vec::new(),
+        )));
 
-        let html_tag = tree.new_node(Node::Element(Element {
-            name: QualName::new(None, ns!(html), LocalName::from("html")),
-            id: None,
-            classes: Default::default(),
-            attrs: Default::default(),
-        }));
+        let html_tag = tree.new_node(Node::Element(Element::new(
+            QualName::new(None, ns!(html), LocalName::from("html")),
+            Vec::new(),
+        )));
 
         html_tag.append(head_tag, &mut tree);
         html_tag.append(body_tag, &mut tree);
@@ -81,6 +111,16 @@ impl Document {
         &self.tree
     }
 
+    pub(crate) fn root(&self) -> NodeId {
+        self.root
+    }
+
+    /// Detaches a node and frees it and its descendants, unlike
+    /// [`Document::remove`], which leaves them in the arena.
+    pub(crate) fn free_subtree(&mut self, node: NodeId) {
+        free_subtree(&mut self.tree, node)
+    }
+
     pub fn select(&self, selector: &str) -> Selection {
         self.select_from(self.root, selector)
     }
@@ -100,6 +140,14 @@ impl Document {
         self.tree.get_mut(node).map(|m| m.get_mut())
     }
 
+    pub fn node_ref(&self, node: NodeId) -> NodeRef<'_> {
+        NodeRef::new(&self.tree, node)
+    }
+
+    pub fn node_mut(&mut self, node: NodeId) -> NodeMut<'_> {
+        NodeMut::new(&mut self.tree, node)
+    }
+
     pub fn remove(&mut self, node: NodeId) {
         node.remove_subtree(&mut self.tree)
     }
@@ -124,10 +172,103 @@ impl Document {
         NodeRef::new(&self.tree, node).inner_html()
     }
 
+    /// Writes the HTML of the whole document, see [`NodeRef::write_html`].
+    pub fn write_html<W: Write>(&self, writer: &mut W, scope: TraversalScope) -> io::Result<()> {
+        NodeRef::new(&self.tree, self.root).write_html(writer, scope)
+    }
+
+    /// Returns the HTML of the whole document in canonical form, see
+    /// [`NodeRef::write_canonical`].
+    pub fn canonical_html(&self) -> String {
+        NodeRef::new(&self.tree, self.root).canonical_html()
+    }
+
+    /// Returns a hash of the canonical HTML, which is stable across runs and
+    /// platforms. Text escaping comes from html5ever, so a release of it
+    /// may change the hash; don't store hashes across dependency upgrades.
+    pub fn content_hash(&self) -> u64 {
+        let mut hasher = ContentHasher::new();
+        NodeRef::new(&self.tree, self.root)
+            .write_canonical(&mut hasher, TraversalScope::IncludeNode)
+            .unwrap();
+        hasher.finish()
+    }
+
+    /// Encodes the whole arena, orphans included, in a compact binary
+    /// format with interned names. Node ids change when it's loaded.
+    pub fn to_snapshot(&self) -> Vec<u8> {
+        let mut buf = Vec::new();
+        self.write_snapshot(&mut buf).unwrap();
+        buf
+    }
+
+    /// Writes the snapshot returned by [`Document::to_snapshot`].
+    pub fn write_snapshot<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        snapshot::write_snapshot(self, writer)
+    }
+
+    /// Converts the whole document to Markdown, see [`to_markdown`].
+    pub fn to_markdown(&self, opts: &MarkdownOptions) -> String {
+        to_markdown(NodeRef::new(&self.tree, self.root), opts)
+    }
+
+    /// Returns the whole document serialized as well-formed XHTML.
+    pub fn xhtml(&self) -> String {
+        NodeRef::new(&self.tree, self.root).xhtml()
+    }
+
+    /// Writes the whole document as XHTML, see [`NodeRef::write_xhtml`].
+    pub fn write_xhtml<W: Write>(&self, writer: &mut W, scope: TraversalScope) -> io::Result<()> {
+        NodeRef::new(&self.tree, self.root).write_xhtml(writer, scope)
+    }
+
+    /// Replaces the children of a node with the parsed HTML fragment.
+    pub fn set_inner_html(&mut self, node: NodeId, html: &str) {
+        set_inner_html(&mut self.tree, node, html)
+    }
+
+    /// Appends the nodes for a CommonMark document, with the GFM tables,
+    /// strikethrough and task list extensions, to a node. Raw HTML is parsed
+    /// in the context of the node it ends up in.
+    #[cfg(feature = "markdown")]
+    pub fn append_markdown(&mut self, parent: NodeId, markdown: &str) {
+        append_markdown(self, parent, markdown)
+    }
+
+    #[cfg(feature = "markdown")]
+    pub(crate) fn append_html(&mut self, parent: NodeId, html: &str) {
+        append_html(&mut self.tree, parent, html)
+    }
+
+    /// Replaces a node with the parsed HTML fragment.
+    pub fn set_outer_html(&mut self, node: NodeId, html: &str) {
+        set_outer_html(&mut self.tree, node, html)
+    }
+
+    /// Returns the HTML of the whole document, indented for readability.
+    pub fn pretty_html(&self, opts: &SerializeOptions) -> String {
+        NodeRef::new(&self.tree, self.root).pretty_html(opts)
+    }
+
+    /// Returns the HTML of the whole document in minified form.
+    pub fn minified_html(&self, opts: &MinifyOptions) -> String {
+        NodeRef::new(&self.tree, self.root).minified_html(opts)
+    }
+
     pub fn text(&self, node: NodeId) -> Text<'_> {
         NodeRef::new(&self.tree, node).text()
     }
 
+    /// Returns the rendered text of a node, see [`NodeRef::inner_text`].
+    pub fn inner_text(&self, node: NodeId) -> String {
+        NodeRef::new(&self.tree, node).inner_text()
+    }
+
+    /// Sets the text content of a node, see [`NodeMut::set_text`].
+    pub fn set_text(&mut self, node: NodeId, text: impl Into<SmolStr>) {
+        self.node_mut(node).set_text(text)
+    }
+
     pub fn create_element(&mut self, name: &str) -> NodeId {
         let name = QualName::new(None, ns!(html), LocalName::from(name));
         let node = Node::Element(Element::new(name, Vec::new()));
@@ -146,6 +287,59 @@ impl Document {
         self.tree.new_node(node)
     }
 
+    pub(crate) fn create_node(&mut self, node: Node) -> NodeId {
+        self.tree.new_node(node)
+    }
+
+    /// Clones a node into a new orphan. If `deep` is true the whole subtree
+    /// is cloned, otherwise only the node itself.
+    pub fn clone_node(&mut self, node: NodeId, deep: bool) -> NodeId {
+        let nodes = collect_subtree(&self.tree, node, deep);
+        build_subtree(&mut self.tree, nodes)
+    }
+
+    /// Copies a node from another document into a new orphan in this one.
+    /// If `deep` is true the whole subtree is copied.
+    pub fn import_node(&mut self, other: &Document, node: NodeId, deep: bool) -> NodeId {
+        let nodes = collect_subtree(&other.tree, node, deep);
+        build_subtree(&mut self.tree, nodes)
+    }
+
+    /// Moves a subtree from another document into a new orphan in this one.
+    /// The nodes are freed from `other`, so `node` is no longer valid there.
+    ///
+    /// Only elements, text and comments can be moved. For anything else,
+    /// like `other`'s document node or doctype, nothing happens and `None`
+    /// is returned.
+    pub fn adopt_node(&mut self, other: &mut Document, node: NodeId) -> Option<NodeId> {
+        let adoptable = matches!(
+            other.get(node),
+            Some(Node::Element(_) | Node::Text(_) | Node::Comment(_))
+        );
+        if node == other.root || !adoptable {
+            return None;
+        }
+
+        let id = self.import_node(other, node, true);
+        free_subtree(&mut other.tree, node);
+        Some(id)
+    }
+
+    /// Merges adjacent text nodes and removes empty ones in the subtree.
+    pub fn normalize(&mut self, node: NodeId) {
+        self.normalize_with(node, false)
+    }
+
+    /// Like [`Document::normalize`], but if `collapse_whitespace` is true,
+    /// whitespace-only text is also collapsed to a single space outside of
+    /// whitespace-preserving elements like `pre` and `textarea`.
+    pub fn normalize_with(&mut self, node: NodeId, collapse_whitespace: bool) {
+        let preserve = node
+            .ancestors(&self.tree)
+            .any(|id| preserves_whitespace(self.tree[id].get()));
+        normalize(&mut self.tree, node, collapse_whitespace && !preserve);
+    }
+
     pub fn orhpans(&self) -> impl Iterator<Item = NodeId> + '_ {
         self.tree.iter_pairs().filter_map(|(id, node)| {
             if id == self.root || node.parent().is_some() {
@@ -164,6 +358,113 @@ impl Document {
     }
 }
 
+/// Detaches a subtree and frees its nodes. `NodeId::remove_subtree` only
+/// detaches it, leaving the nodes in the arena as orphans.
+pub(crate) fn free_subtree(tree: &mut Arena<Node>, node: NodeId) {
+    let nodes = node.descendants(tree).collect::<Vec<_>>();
+    node.detach(tree);
+    // Children come before their parents, so each node is a leaf when it's
+    // removed.
+    for node in nodes.into_iter().rev() {
+        node.remove(tree);
+    }
+}
+
+/// Collects a subtree in document order, each node paired with the index of
+/// its parent in the returned list.
+fn collect_subtree(tree: &Arena<Node>, node: NodeId, deep: bool) -> Vec<(Node, Option<usize>)> {
+    let mut nodes = Vec::new();
+    let mut parents = Vec::new();
+
+    for edge in node.traverse(tree) {
+        match edge {
+            NodeEdge::Start(id) => {
+                nodes.push((tree[id].get().clone(), parents.last().copied()));
+                parents.push(nodes.len() - 1);
+                if !deep {
+                    break;
+                }
+            }
+            NodeEdge::End(_) => {
+                parents.pop();
+            }
+        }
+    }
+
+    // Template elements always carry their contents fragment.
+    if !deep && is_template(&nodes[0].0) {
+        nodes.push((Node::Fragment, Some(0)));
+    }
+
+    nodes
+}
+
+/// Inserts nodes produced by `collect_subtree` into `tree`, returning the
+/// id of the (orphaned) root.
+fn build_subtree(tree: &mut Arena<Node>, nodes: Vec<(Node, Option<usize>)>) -> NodeId {
+    let mut ids: Vec<NodeId> = Vec::with_capacity(nodes.len());
+
+    for (node, parent) in nodes {
+        let id = tree.new_node(node);
+        if let Some(parent) = parent {
+            ids[parent].append(id, tree);
+        }
+        ids.push(id);
+    }
+
+    ids[0]
+}
+
+fn normalize(tree: &mut Arena<Node>, node: NodeId, collapse_whitespace: bool) {
+    let children = node.children(tree).collect::<Vec<_>>();
+    let mut run: Option<NodeId> = None;
+
+    for child in children {
+        match (tree[child].get(), run) {
+            (Node::Text(text), Some(first)) => {
+                let text = text.text.clone();
+                if let Node::Text(ref mut t) = tree[first].get_mut() {
+                    t.concat(&text);
+                }
+                child.remove(tree);
+            }
+            (Node::Text(_), None) => run = Some(child),
+            (node, _) => {
+                let collapse = collapse_whitespace && !preserves_whitespace(node);
+                if let Some(first) = run.take() {
+                    normalize_text(tree, first, collapse_whitespace);
+                }
+                normalize(tree, child, collapse);
+            }
+        }
+    }
+
+    if let Some(first) = run {
+        normalize_text(tree, first, collapse_whitespace);
+    }
+}
+
+fn normalize_text(tree: &mut Arena<Node>, node: NodeId, collapse_whitespace: bool) {
+    let Some(text) = tree[node].get_mut().as_text_mut() else {
+        return;
+    };
+
+    if text.is_empty() {
+        node.remove(tree);
+    } else if collapse_whitespace
+        && text
+            .trim_matches(|c: char| c.is_ascii_whitespace())
+            .is_empty()
+    {
+        text.set_text(" ");
+    }
+}
+
+pub(crate) fn is_template(node: &Node) -> bool {
+    node.as_element()
+        .is_some_and(|e| e.name.expanded() == expanded_name!(html "template"))
+}
+
 impl core::ops::Index<NodeId> for Document {
     type Output = Node;
     fn index(&self, index: NodeId) -> &Self::Output {
@@ -182,3 +483,129 @@ impl fmt::Display for Document {
         write!(f, "{}", NodeRef::new(&self.tree, self.root).html())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn first(dom: &Document, selector: &str) -> NodeId {
+        dom.select(selector).get(0).expect(selector)
+    }
+
+    #[test]
+    fn clone_node_shallow_and_deep() {
+        // A single attribute, as their order depends on `deterministic`.
+        let mut dom = Document::parse(r#"<div id="a"><p>hi</p></div>"#);
+        let div = first(&dom, "div");
+
+        let shallow = dom.clone_node(div, false);
+        assert_eq!(
+            NodeRef::new(&dom.tree, shallow).html(),
+            r#"<div id="a"></div>"#
+        );
+
+        let deep = dom.clone_node(div, true);
+        assert_eq!(
+            NodeRef::new(&dom.tree, deep).html(),
+            r#"<div id="a"><p>hi</p></div>"#
+        );
+        assert!(NodeRef::new(&dom.tree, deep).parent().is_none());
+    }
+
+    #[test]
+    fn clone_node_keeps_template_contents() {
+        let mut dom = Document::parse("<template><b>x</b></template>");
+        let template = first(&dom, "template");
+
+        let deep = dom.clone_node(template, true);
+        assert_eq!(
+            NodeRef::new(&dom.tree, deep).html(),
+            "<template><b>x</b></template>"
+        );
+
+        let shallow = dom.clone_node(template, false);
+        assert!(dom[dom.children(shallow).next().unwrap()].is_fragment());
+    }
+
+    #[test]
+    fn import_node_copies_between_documents() {
+        let source = Document::parse("<ul><li>one</li><li>two</li></ul>");
+        let mut dom = Document::new_html5();
+
+        let list = dom.import_node(&source, first(&source, "ul"), true);
+        dom.append(first(&dom, "body"), list);
+
+        assert_eq!(
+            dom.inner_html(first(&dom, "body")),
+            "<ul><li>one</li><li>two</li></ul>"
+        );
+        assert_eq!(source.select("li").len(), 2);
+    }
+
+    #[test]
+    fn adopt_node_frees_the_source_nodes() {
+        let mut source = Document::parse("<p>a <b>b</b></p>");
+        let mut dom = Document::new_html5();
+        let before = source.tree.count();
+
+        let p = first(&source, "p");
+        let p = dom.adopt_node(&mut source, p).unwrap();
+        assert_eq!(NodeRef::new(&dom.tree, p).html(), "<p>a <b>b</b></p>");
+        assert_eq!(source.tree.count(), before - 4);
+        assert_eq!(source.select("p").len(), 0);
+    }
+
+    #[test]
+    fn adopt_node_rejects_roots_and_doctypes() {
+        let mut source = Document::parse("<!DOCTYPE html><p>a</p>");
+        let mut dom = Document::new_html5();
+        let before = source.tree.count();
+
+        let root = source.root;
+        assert_eq!(dom.adopt_node(&mut source, root), None);
+        let doctype = source.children(source.root).next().unwrap();
+        assert_eq!(dom.adopt_node(&mut source, doctype), None);
+
+        assert_eq!(source.tree.count(), before);
+        assert_eq!(
+            source.to_string(),
+            "<!DOCTYPE html><html><head></head><body><p>a</p></body></html>"
+        );
+    }
+
+    #[test]
+    fn normalize_merges_and_drops_text() {
+        let mut dom = Document::parse("<div></div>");
+        let div = first(&dom, "div");
+        for text in ["a", "", "b"] {
+            let text = dom.create_text(text);
+            dom.append(div, text);
+        }
+        let em = dom.create_element("em");
+        dom.append(div, em);
+        let empty = dom.create_text("");
+        dom.append(div, empty);
+
+        dom.normalize(div);
+        assert_eq!(dom.children(div).count(), 2);
+        assert_eq!(dom.inner_html(div), "ab<em></em>");
+    }
+
+    #[test]
+    fn normalize_with_collapses_whitespace_outside_pre() {
+        let mut dom = Document::parse("<div><p>a</p>\n\n  <pre>  \n  </pre></div>");
+        let div = first(&dom, "div");
+
+        dom.normalize_with(div, true);
+        assert_eq!(dom.inner_html(div), "<p>a</p> <pre>  \n  </pre>");
+    }
+
+    #[test]
+    fn normalize_with_keeps_non_breaking_spaces() {
+        let mut dom = Document::parse("<div><p>a</p>\u{a0}<p>b</p></div>");
+        let div = first(&dom, "div");
+
+        dom.normalize_with(div, true);
+        assert_eq!(dom.inner_html(div), "<p>a</p>&nbsp;<p>b</p>");
+    }
+}
@@ -0,0 +1,155 @@
+use generational_indextree::{Arena, NodeId};
+use html5ever::interface::QuirksMode;
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{node::Node, NodeRef};
+
+use super::Document;
+
+/// A document as its root node, nested as `{"type", ..., "children"}`,
+/// plus the quirks mode it was parsed in.
+#[derive(Serialize)]
+struct DocumentRepr<'a> {
+    #[serde(flatten)]
+    root: Tree<'a>,
+    quirks_mode: &'static str,
+}
+
+#[derive(Deserialize)]
+struct OwnedDocumentRepr {
+    #[serde(flatten)]
+    root: OwnedTree,
+    #[serde(default)]
+    quirks_mode: Option<String>,
+}
+
+/// A node with the fields of its [`Node`] and its children.
+#[derive(Serialize)]
+struct Tree<'a> {
+    #[serde(flatten)]
+    node: &'a Node,
+    #[serde(skip_serializing_if = "Children::is_empty")]
+    children: Children<'a>,
+}
+
+impl<'a> Tree<'a> {
+    fn new(node: NodeRef<'a>) -> Self {
+        Tree {
+            node: node.node(),
+            children: Children(node),
+        }
+    }
+}
+
+struct Children<'a>(NodeRef<'a>);
+
+impl<'a> Children<'a> {
+    fn is_empty(&self) -> bool {
+        self.0.children().next().is_none()
+    }
+}
+
+impl<'a> Serialize for Children<'a> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.0.children().map(Tree::new))
+    }
+}
+
+#[derive(Deserialize)]
+struct OwnedTree {
+    #[serde(flatten)]
+    node: Node,
+    #[serde(default)]
+    children: Vec<OwnedTree>,
+}
+
+impl OwnedTree {
+    fn build(self, tree: &mut Arena<Node>) -> NodeId {
+        let id = tree.new_node(self.node);
+        for child in self.children {
+            let child = child.build(tree);
+            id.append(child, tree);
+        }
+        id
+    }
+}
+
+impl Serialize for Document {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        DocumentRepr {
+            quirks_mode: match self.quirks {
+                QuirksMode::Quirks => "quirks",
+                QuirksMode::LimitedQuirks => "limited-quirks",
+                QuirksMode::NoQuirks => "no-quirks",
+            },
+            root: Tree::new(NodeRef::new(&self.tree, self.root)),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Document {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let repr = OwnedDocumentRepr::deserialize(deserializer)?;
+
+        let quirks = match repr.quirks_mode.as_deref() {
+            None | Some("no-quirks") => QuirksMode::NoQuirks,
+            Some("limited-quirks") => QuirksMode::LimitedQuirks,
+            Some("quirks") => QuirksMode::Quirks,
+            Some(other) => {
+                return Err(de::Error::unknown_variant(
+                    other,
+                    &["no-quirks", "limited-quirks", "quirks"],
+                ))
+            }
+        };
+
+        if !repr.root.node.is_document() {
+            return Err(de::Error::custom("expected a document node at the root"));
+        }
+
+        let mut tree = Arena::new();
+        let root = repr.root.build(&mut tree);
+        Ok(Document::new(tree, root, quirks))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Document;
+
+    #[test]
+    fn documents_round_trip_through_json() {
+        let html = "<!DOCTYPE html><html><head><template><b>t</b></template></head>\
+                    <body><p id=\"x\" class=\"a\">a<!--c--></p></body></html>";
+        let dom = Document::parse(html);
+
+        let json = serde_json::to_string(&dom).unwrap();
+        let copy: Document = serde_json::from_str(&json).unwrap();
+        assert_eq!(copy.to_string(), dom.to_string());
+        assert_eq!(copy.select("#x.a").len(), 1);
+    }
+
+    #[test]
+    fn quirks_mode_is_kept() {
+        let dom = Document::parse("<p>no doctype</p>");
+        let json = serde_json::to_value(&dom).unwrap();
+        assert_eq!(json["quirks_mode"], "quirks");
+        assert_eq!(json["children"][0]["type"], "element");
+
+        let copy: Document = serde_json::from_value(json).unwrap();
+        assert_eq!(
+            serde_json::to_value(&copy).unwrap()["quirks_mode"],
+            "quirks"
+        );
+    }
+
+    #[test]
+    fn invalid_documents_are_rejected() {
+        let text = r#"{"type": "text", "text": "a"}"#;
+        assert!(serde_json::from_str::<Document>(text).is_err());
+
+        let quirks = r#"{"type": "document", "quirks_mode": "sometimes"}"#;
+        assert!(serde_json::from_str::<Document>(quirks).is_err());
+    }
+}
@@ -210,10 +210,7 @@ impl TreeSink for DocumentBuilder {
         };
 
         for attr in attrs {
-            element
-                .attrs
-                .entry(attr.name)
-                .or_insert_with(|| attr.value.into());
+            element.add_attr_if_missing(attr.name, attr.value.into());
         }
     }
 
@@ -0,0 +1,382 @@
+//! A compact binary encoding of a document's arena.
+//!
+//! Layout, with integers as LEB128 varints unless noted:
+//!
+//! ```text
+//! magic "DJSN" | version: u16 le | quirks: u8
+//! string count | (len | utf-8 bytes)*       names, namespaces and prefixes
+//! node count | node*
+//! ```
+//!
+//! Each node is its kind byte, its parent's position plus one (zero for
+//! none) and its payload. The root comes first, followed by the orphans,
+//! each tree in document order, so that appending every node to its parent
+//! restores the sibling order. Node ids aren't preserved, as the arena
+//! hands out new ones on load.
+
+use std::collections::HashMap;
+use std::io::{self, Read, Write};
+
+use generational_indextree::{Arena, NodeEdge, NodeId};
+use html5ever::interface::QuirksMode;
+use html5ever::{Attribute, LocalName, Namespace, Prefix, QualName};
+
+use crate::error::SnapshotError;
+use crate::node::{Comment, Doctype, Element, Node, ProcessingInstruction, Text};
+
+use super::Document;
+
+const MAGIC: &[u8; 4] = b"DJSN";
+const VERSION: u16 = 1;
+
+const DOCUMENT: u8 = 0;
+const FRAGMENT: u8 = 1;
+const DOCTYPE: u8 = 2;
+const COMMENT: u8 = 3;
+const TEXT: u8 = 4;
+const ELEMENT: u8 = 5;
+const PROCESSING_INSTRUCTION: u8 = 6;
+
+pub(crate) fn write_snapshot<W: Write>(doc: &Document, writer: &mut W) -> io::Result<()> {
+    let mut encoder = Encoder::default();
+
+    let orphans: Vec<_> = doc.orhpans().collect();
+    let mut count = 0;
+    for root in std::iter::once(doc.root).chain(orphans) {
+        count += encoder.tree(&doc.tree, root);
+    }
+
+    writer.write_all(MAGIC)?;
+    writer.write_all(&VERSION.to_le_bytes())?;
+    writer.write_all(&[match doc.quirks {
+        QuirksMode::NoQuirks => 0,
+        QuirksMode::LimitedQuirks => 1,
+        QuirksMode::Quirks => 2,
+    }])?;
+
+    let mut header = Vec::new();
+    write_varint(&mut header, encoder.strings.len());
+    for string in &encoder.strings {
+        write_str(&mut header, string);
+    }
+    write_varint(&mut header, count);
+
+    writer.write_all(&header)?;
+    writer.write_all(&encoder.body)
+}
+
+pub(crate) fn read_snapshot(bytes: &[u8]) -> Result<Document, SnapshotError> {
+    let mut decoder = Decoder { bytes, pos: 0 };
+
+    if decoder.take(MAGIC.len())? != MAGIC {
+        return Err(SnapshotError::BadMagic);
+    }
+
+    let version = u16::from_le_bytes([decoder.byte()?, decoder.byte()?]);
+    if version != VERSION {
+        return Err(SnapshotError::UnsupportedVersion(version));
+    }
+
+    let quirks = match decoder.byte()? {
+        0 => QuirksMode::NoQuirks,
+        1 => QuirksMode::LimitedQuirks,
+        2 => QuirksMode::Quirks,
+        _ => return Err(SnapshotError::Corrupt("unknown quirks mode")),
+    };
+
+    let string_count = decoder.varint()?;
+    let mut strings = Vec::with_capacity(string_count.min(bytes.len()));
+    for _ in 0..string_count {
+        strings.push(decoder.str()?);
+    }
+
+    let node_count = decoder.varint()?;
+    let mut tree = Arena::with_capacity(node_count.min(bytes.len()));
+    let mut ids: Vec<NodeId> = Vec::with_capacity(node_count.min(bytes.len()));
+
+    for idx in 0..node_count {
+        let kind = decoder.byte()?;
+        let parent = match decoder.varint()? {
+            0 => None,
+            // Parents always come first, which also rules out cycles.
+            n if n <= idx => Some(ids[n - 1]),
+            _ => return Err(SnapshotError::Corrupt("parent after child")),
+        };
+
+        let node = match kind {
+            DOCUMENT => Node::Document,
+            FRAGMENT => Node::Fragment,
+            DOCTYPE => Node::Doctype(Doctype {
+                name: decoder.str()?.into(),
+                public_id: decoder.str()?.into(),
+                system_id: decoder.str()?.into(),
+            }),
+            COMMENT => Node::Comment(Comment {
+                comment: decoder.str()?.into(),
+            }),
+            TEXT => Node::Text(Text {
+                text: decoder.str()?.into(),
+            }),
+            ELEMENT => {
+                let name = decoder.name(&strings)?;
+                let attr_count = decoder.varint()?;
+                let mut attrs = Vec::with_capacity(attr_count.min(bytes.len()));
+                for _ in 0..attr_count {
+                    attrs.push(Attribute {
+                        name: decoder.name(&strings)?,
+                        value: decoder.str()?.into(),
+                    });
+                }
+                Node::Element(Element::new(name, attrs))
+            }
+            PROCESSING_INSTRUCTION => Node::ProcessingInstruction(ProcessingInstruction {
+                target: decoder.str()?.to_string(),
+                data: decoder.str()?.to_string(),
+            }),
+            _ => return Err(SnapshotError::Corrupt("unknown node kind")),
+        };
+
+        let id = tree.new_node(node);
+        if let Some(parent) = parent {
+            parent.append(id, &mut tree);
+        }
+        ids.push(id);
+    }
+
+    if decoder.pos != bytes.len() {
+        return Err(SnapshotError::Corrupt("trailing bytes"));
+    }
+
+    let root = match ids.first() {
+        Some(&root) if tree[root].get().is_document() => root,
+        _ => return Err(SnapshotError::Corrupt("missing document root")),
+    };
+
+    Ok(Document::new(tree, root, quirks))
+}
+
+pub(crate) fn read_snapshot_from<R: Read>(reader: &mut R) -> Result<Document, SnapshotError> {
+    let mut bytes = Vec::new();
+    reader.read_to_end(&mut bytes)?;
+    read_snapshot(&bytes)
+}
+
+#[derive(Default)]
+struct Encoder<'a> {
+    body: Vec<u8>,
+    strings: Vec<&'a str>,
+    symbols: HashMap<&'a str, usize>,
+    /// Positions of the nodes written so far.
+    positions: HashMap<NodeId, usize>,
+}
+
+impl<'a> Encoder<'a> {
+    /// Writes a tree in document order, returning its number of nodes.
+    fn tree(&mut self, tree: &'a Arena<Node>, root: NodeId) -> usize {
+        let mut count = 0;
+        for edge in root.traverse(tree) {
+            let NodeEdge::Start(id) = edge else {
+                continue;
+            };
+
+            let parent = match tree[id].parent() {
+                Some(parent) if id != root => self.positions[&parent] + 1,
+                _ => 0,
+            };
+            self.positions.insert(id, self.positions.len());
+            count += 1;
+
+            self.node(tree[id].get(), parent);
+        }
+        count
+    }
+
+    fn node(&mut self, node: &'a Node, parent: usize) {
+        let kind = match node {
+            Node::Document => DOCUMENT,
+            Node::Fragment => FRAGMENT,
+            Node::Doctype(_) => DOCTYPE,
+            Node::Comment(_) => COMMENT,
+            Node::Text(_) => TEXT,
+            Node::Element(_) => ELEMENT,
+            Node::ProcessingInstruction(_) => PROCESSING_INSTRUCTION,
+        };
+        self.body.push(kind);
+        write_varint(&mut self.body, parent);
+
+        match node {
+            Node::Document | Node::Fragment => {}
+            Node::Doctype(doctype) => {
+                write_str(&mut self.body, doctype.name());
+                write_str(&mut self.body, doctype.public_id());
+                write_str(&mut self.body, doctype.system_id());
+            }
+            Node::Comment(comment) => write_str(&mut self.body, comment),
+            Node::Text(text) => write_str(&mut self.body, text),
+            Node::Element(element) => {
+                self.name(&element.name);
+                write_varint(&mut self.body, element.attrs.len());
+                for (name, value) in element.attrs.iter() {
+                    self.name(name);
+                    write_str(&mut self.body, value);
+                }
+            }
+            Node::ProcessingInstruction(pi) => {
+                write_str(&mut self.body, &pi.target);
+                write_str(&mut self.body, &pi.data);
+            }
+        }
+    }
+
+    /// Writes a name as symbols: the prefix plus one, or zero for none, the
+    /// namespace and the local name.
+    fn name(&mut self, name: &'a QualName) {
+        let prefix = name.prefix.as_deref().map_or(0, |p| self.symbol(p) + 1);
+        write_varint(&mut self.body, prefix);
+        let ns = self.symbol(&name.ns);
+        write_varint(&mut self.body, ns);
+        let local = self.symbol(&name.local);
+        write_varint(&mut self.body, local);
+    }
+
+    fn symbol(&mut self, string: &'a str) -> usize {
+        if let Some(&symbol) = self.symbols.get(string) {
+            return symbol;
+        }
+        let symbol = self.strings.len();
+        self.strings.push(string);
+        self.symbols.insert(string, symbol);
+        symbol
+    }
+}
+
+struct Decoder<'b> {
+    bytes: &'b [u8],
+    pos: usize,
+}
+
+impl<'b> Decoder<'b> {
+    fn take(&mut self, len: usize) -> Result<&'b [u8], SnapshotError> {
+        let end = self
+            .pos
+            .checked_add(len)
+            .filter(|end| *end <= self.bytes.len())
+            .ok_or(SnapshotError::Corrupt("unexpected end of snapshot"))?;
+        let bytes = &self.bytes[self.pos..end];
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    fn byte(&mut self) -> Result<u8, SnapshotError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn varint(&mut self) -> Result<usize, SnapshotError> {
+        let mut value: usize = 0;
+        for shift in (0..usize::BITS).step_by(7) {
+            let byte = self.byte()?;
+            let part = usize::from(byte & 0x7f);
+            if shift > 0 && part >> (usize::BITS - shift) != 0 {
+                break;
+            }
+            value |= part << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(SnapshotError::Corrupt("varint overflow"))
+    }
+
+    fn str(&mut self) -> Result<&'b str, SnapshotError> {
+        let len = self.varint()?;
+        std::str::from_utf8(self.take(len)?).map_err(|_| SnapshotError::Corrupt("invalid utf-8"))
+    }
+
+    fn symbol(&mut self, strings: &[&'b str]) -> Result<&'b str, SnapshotError> {
+        strings
+            .get(self.varint()?)
+            .copied()
+            .ok_or(SnapshotError::Corrupt("unknown string"))
+    }
+
+    fn name(&mut self, strings: &[&'b str]) -> Result<QualName, SnapshotError> {
+        let prefix = match self.varint()? {
+            0 => None,
+            n => Some(Prefix::from(
+                *strings
+                    .get(n - 1)
+                    .ok_or(SnapshotError::Corrupt("unknown string"))?,
+            )),
+        };
+        let ns = Namespace::from(self.symbol(strings)?);
+        let local = LocalName::from(self.symbol(strings)?);
+        Ok(QualName::new(prefix, ns, local))
+    }
+}
+
+fn write_varint(buf: &mut Vec<u8>, mut value: usize) {
+    while value >= 0x80 {
+        buf.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    buf.push(value as u8);
+}
+
+fn write_str(buf: &mut Vec<u8>, string: &str) {
+    write_varint(buf, string.len());
+    buf.extend_from_slice(string.as_bytes());
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::SnapshotError;
+    use crate::Document;
+
+    #[test]
+    fn documents_round_trip() {
+        let html = "<!DOCTYPE html PUBLIC \"-//W3C//DTD HTML 4.01//EN\"><html><head>\
+                    <template><b>t</b></template></head><body><p id=\"x\">a<!--c-->\
+                    <svg><use xlink:href=\"#y\"></use></svg></p></body></html>";
+        let mut dom = Document::parse(html);
+        dom.create_text("orphan");
+
+        let copy = Document::from_snapshot(&dom.to_snapshot()).unwrap();
+        assert_eq!(copy.to_string(), dom.to_string());
+        assert_eq!(copy.orhpans().count(), 1);
+        assert_eq!(copy.select("#x").len(), 1);
+        assert_eq!(copy.to_snapshot(), dom.to_snapshot());
+
+        let mut reader = &dom.to_snapshot()[..];
+        let read = Document::read_snapshot(&mut reader).unwrap();
+        assert_eq!(read.to_string(), dom.to_string());
+    }
+
+    #[test]
+    fn invalid_snapshots_are_rejected() {
+        let bytes = Document::parse("<p>a</p>").to_snapshot();
+
+        assert!(matches!(
+            Document::from_snapshot(b"<p>a</p>"),
+            Err(SnapshotError::BadMagic)
+        ));
+
+        let mut version = bytes.clone();
+        version[4] = 9;
+        assert!(matches!(
+            Document::from_snapshot(&version),
+            Err(SnapshotError::UnsupportedVersion(9))
+        ));
+
+        assert!(matches!(
+            Document::from_snapshot(&bytes[..bytes.len() - 1]),
+            Err(SnapshotError::Corrupt(_))
+        ));
+
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        assert!(matches!(
+            Document::from_snapshot(&trailing),
+            Err(SnapshotError::Corrupt("trailing bytes"))
+        ));
+    }
+}
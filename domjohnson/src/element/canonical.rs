@@ -0,0 +1,231 @@
+use std::borrow::Cow;
+use std::io::{self, Write};
+
+use html5ever::serialize::{
+    AttrRef, HtmlSerializer, Serialize, SerializeOpts, Serializer, TraversalScope,
+};
+use html5ever::{expanded_name, local_name, namespace_url, ns, QualName};
+
+use crate::node::Style;
+
+use super::{
+    kind::{collapse_whitespace, is_rendered_block_name, preserves_whitespace_name},
+    node_ref::NodeRef,
+};
+
+/// Writes `node` in canonical form, so that documents that only differ in
+/// attribute order, quoting, character references or insignificant
+/// whitespace are written the same.
+///
+/// Attributes are sorted by namespace and name, `class` tokens are sorted
+/// and deduplicated and `style` declarations are normalized. Whitespace
+/// runs collapse to a single space and whitespace next to rendered block
+/// elements or inside `head` is dropped, except inside `pre`, `textarea`,
+/// `script` and `style`.
+pub(crate) fn write_canonical<W: Write>(
+    node: NodeRef<'_>,
+    writer: W,
+    traversal_scope: TraversalScope,
+) -> io::Result<()> {
+    let opts = SerializeOpts {
+        scripting_enabled: false,
+        traversal_scope: TraversalScope::IncludeNode,
+        create_missing_parent: false,
+    };
+
+    let mut serializer = Canonicalizer {
+        inner: HtmlSerializer::new(writer, opts),
+        preserve: Vec::new(),
+        in_head: false,
+        after_block: true,
+        pending_space: false,
+    };
+    Serialize::serialize(&node, &mut serializer, traversal_scope)
+}
+
+struct Canonicalizer<W: Write> {
+    inner: HtmlSerializer<W>,
+    /// Whether whitespace is kept, for each open element.
+    preserve: Vec<bool>,
+    /// Whether the open elements include `head`, where nothing is rendered.
+    in_head: bool,
+    after_block: bool,
+    /// A collapsed space held back until the next event shows whether it's
+    /// next to a block element.
+    pending_space: bool,
+}
+
+impl<W: Write> Canonicalizer<W> {
+    fn preserving(&self) -> bool {
+        self.preserve.last().copied().unwrap_or(false)
+    }
+
+    fn boundary(&mut self, block: bool) -> io::Result<()> {
+        let space = std::mem::take(&mut self.pending_space);
+        if space && !block && !self.in_head {
+            self.inner.write_text(" ")?;
+        }
+        Ok(())
+    }
+}
+
+impl<W: Write> Serializer for Canonicalizer<W> {
+    fn start_elem<'a, AttrIter>(&mut self, name: QualName, attrs: AttrIter) -> io::Result<()>
+    where
+        AttrIter: Iterator<Item = AttrRef<'a>>,
+    {
+        let block = is_rendered_block_name(&name);
+        self.boundary(block)?;
+        self.after_block = block;
+        self.in_head |= name.expanded() == expanded_name!(html "head");
+        self.preserve
+            .push(self.preserving() || preserves_whitespace_name(&name));
+
+        let mut attrs: Vec<_> = attrs
+            .map(|(attr, value)| (attr, canonical_value(attr, value)))
+            .collect();
+        attrs.sort_by(|(a, _), (b, _)| (&a.ns, &a.local).cmp(&(&b.ns, &b.local)));
+
+        let attrs = attrs.iter().map(|(attr, value)| (*attr, &value[..]));
+        self.inner.start_elem(name, attrs)
+    }
+
+    fn end_elem(&mut self, name: QualName) -> io::Result<()> {
+        let block = is_rendered_block_name(&name);
+        self.boundary(block)?;
+        self.after_block = block;
+        if name.expanded() == expanded_name!(html "head") {
+            self.in_head = false;
+        }
+        self.preserve.pop();
+        self.inner.end_elem(name)
+    }
+
+    fn write_text(&mut self, text: &str) -> io::Result<()> {
+        if self.preserving() {
+            self.boundary(false)?;
+            self.after_block = false;
+            return self.inner.write_text(text);
+        }
+
+        let collapsed = collapse_whitespace(text);
+        let body = collapsed.trim_matches(' ');
+
+        if collapsed.starts_with(' ') && !self.after_block {
+            self.pending_space = true;
+        }
+        if body.is_empty() {
+            return Ok(());
+        }
+
+        self.boundary(false)?;
+        self.after_block = false;
+        self.inner.write_text(body)?;
+        self.pending_space = collapsed.ends_with(' ');
+        Ok(())
+    }
+
+    fn write_comment(&mut self, text: &str) -> io::Result<()> {
+        self.boundary(false)?;
+        self.inner.write_comment(text)
+    }
+
+    fn write_doctype(&mut self, name: &str) -> io::Result<()> {
+        self.pending_space = false;
+        self.after_block = true;
+        self.inner.write_doctype(name)
+    }
+
+    fn write_processing_instruction(&mut self, target: &str, data: &str) -> io::Result<()> {
+        self.boundary(false)?;
+        self.inner.write_processing_instruction(target, data)
+    }
+}
+
+/// Normalizes the values of attributes whose syntax is order- or
+/// whitespace-insensitive.
+fn canonical_value<'a>(attr: &QualName, value: &'a str) -> Cow<'a, str> {
+    if attr.ns != ns!() {
+        return Cow::Borrowed(value);
+    }
+
+    match attr.local {
+        local_name!("class") => {
+            let mut classes: Vec<_> = value.split_ascii_whitespace().collect();
+            classes.sort_unstable();
+            classes.dedup();
+            Cow::Owned(classes.join(" "))
+        }
+        local_name!("style") => Cow::Owned(Style::parse(value).to_string()),
+        _ => Cow::Borrowed(value),
+    }
+}
+
+/// A 64-bit FNV-1a hasher, which unlike the standard library hashers is
+/// stable across releases and platforms.
+pub(crate) struct ContentHasher(u64);
+
+impl ContentHasher {
+    pub(crate) fn new() -> Self {
+        ContentHasher(0xcbf2_9ce4_8422_2325)
+    }
+
+    pub(crate) fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+impl Write for ContentHasher {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        for &byte in buf {
+            self.0 ^= u64::from(byte);
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Document;
+
+    #[test]
+    fn equivalent_markup_is_written_the_same() {
+        let a = Document::parse("<div class='b a b' id=x>\n  <p>one   two</p>\n</div>");
+        let b = Document::parse("<div id=\"x\" class=\"a b\"><p>one two</p></div>");
+        assert_eq!(a.canonical_html(), b.canonical_html());
+        assert_eq!(a.content_hash(), b.content_hash());
+    }
+
+    #[test]
+    fn significant_differences_are_kept() {
+        let a = Document::parse("<p>one two</p>");
+        let b = Document::parse("<p>one<b>two</b></p>");
+        let c = Document::parse("<pre>one  two</pre>");
+        let d = Document::parse("<pre>one two</pre>");
+        assert_ne!(a.content_hash(), b.content_hash());
+        assert_ne!(c.canonical_html(), d.canonical_html());
+    }
+
+    #[test]
+    fn whitespace_next_to_unrendered_elements_is_kept() {
+        let a = Document::parse("<p>a <script>s</script>b</p>");
+        let b = Document::parse("<p>a<script>s</script>b</p>");
+        assert_ne!(a.content_hash(), b.content_hash());
+
+        let a = Document::parse("<head>\n  <meta charset=utf-8>\n  <title>T</title>\n</head>");
+        let b = Document::parse("<head><meta charset=utf-8><title>T</title></head>");
+        assert_eq!(a.content_hash(), b.content_hash());
+    }
+
+    #[test]
+    fn styles_are_normalized() {
+        let a = Document::parse("<p style='color: red;  margin:0'>a</p>");
+        let b = Document::parse("<p style=\"color:red;margin: 0\">a</p>");
+        assert_eq!(a.canonical_html(), b.canonical_html());
+    }
+}
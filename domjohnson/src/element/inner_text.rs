@@ -0,0 +1,188 @@
+use html5ever::{local_name, namespace_url, ns};
+
+use crate::node::Node;
+
+use super::{
+    kind::{is_block_name, preserves_whitespace_name},
+    node_ref::NodeRef,
+};
+
+/// Returns the text of `node` roughly as the innerText algorithm renders
+/// it, assuming default styles since there's no CSS.
+pub(crate) fn inner_text(node: NodeRef<'_>) -> String {
+    let mut text = InnerText {
+        out: String::new(),
+        breaks: 0,
+        space: false,
+    };
+    text.node(node, false);
+    text.out
+}
+
+struct InnerText {
+    out: String,
+    /// Line breaks required before the next text, which are dropped at the
+    /// start and end of the output.
+    breaks: usize,
+    /// A collapsed space, written only if more text follows on the same
+    /// line.
+    space: bool,
+}
+
+impl InnerText {
+    fn node(&mut self, node: NodeRef<'_>, preserve: bool) {
+        let element = match node.node() {
+            Node::Document | Node::Fragment => return self.children(node, preserve),
+            Node::Text(text) => return self.text(text, preserve),
+            Node::Element(element) => element,
+            _ => return,
+        };
+
+        if element.hidden() {
+            return;
+        }
+
+        if element.name.ns != ns!(html) {
+            return self.children(node, preserve);
+        }
+
+        match element.name.local {
+            local_name!("script")
+            | local_name!("style")
+            | local_name!("template")
+            | local_name!("head")
+            | local_name!("noscript") => {}
+            local_name!("br") => self.write("\n"),
+            local_name!("p") => {
+                self.require(2);
+                self.children(node, preserve);
+                self.require(2);
+            }
+            local_name!("td") | local_name!("th") => {
+                self.children(node, preserve);
+                if node.next_siblings().skip(1).any(|s| is_cell(&s)) {
+                    self.write("\t");
+                }
+            }
+            local_name!("tr") => {
+                self.children(node, preserve);
+                self.require(1);
+            }
+            _ => {
+                let preserve = preserve || preserves_whitespace_name(&element.name);
+                let block = is_block_name(&element.name);
+                if block {
+                    self.require(1);
+                }
+                self.children(node, preserve);
+                if block {
+                    self.require(1);
+                }
+            }
+        }
+    }
+
+    fn children(&mut self, node: NodeRef<'_>, preserve: bool) {
+        for child in node.children() {
+            self.node(child, preserve);
+        }
+    }
+
+    fn text(&mut self, text: &str, preserve: bool) {
+        if preserve {
+            if !text.is_empty() {
+                self.write(text);
+            }
+            return;
+        }
+
+        let mut words = text.split_ascii_whitespace();
+        if text.starts_with(|c: char| c.is_ascii_whitespace()) {
+            self.space = true;
+        }
+        if let Some(word) = words.next() {
+            self.write(word);
+            for word in words {
+                self.space = true;
+                self.write(word);
+            }
+            self.space = text.ends_with(|c: char| c.is_ascii_whitespace());
+        }
+    }
+
+    /// Writes text after the pending line breaks or space. Neither is
+    /// written at the very start, and spaces aren't written next to line
+    /// breaks or tabs.
+    fn write(&mut self, text: &str) {
+        let breaks = std::mem::take(&mut self.breaks);
+        let space = std::mem::take(&mut self.space);
+
+        if !self.out.is_empty() {
+            let existing = self.out.len() - self.out.trim_end_matches('\n').len();
+            for _ in existing..breaks {
+                self.out.push('\n');
+            }
+            let line_start = self.out.ends_with(['\n', '\t']) || text.starts_with(['\n', '\t']);
+            if space && breaks == 0 && !line_start {
+                self.out.push(' ');
+            }
+        }
+        self.out.push_str(text);
+    }
+
+    fn require(&mut self, breaks: usize) {
+        self.breaks = self.breaks.max(breaks);
+    }
+}
+
+fn is_cell(node: &NodeRef<'_>) -> bool {
+    node.as_element().is_some_and(|e| {
+        e.name.ns == ns!(html) && matches!(e.name.local, local_name!("td") | local_name!("th"))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Document;
+
+    fn inner_text(html: &str) -> String {
+        let dom = Document::parse(html);
+        let body = dom.select("body").get(0).unwrap();
+        dom.inner_text(body)
+    }
+
+    #[test]
+    fn whitespace_collapses_and_blocks_break_lines() {
+        assert_eq!(
+            inner_text("<div>  Hello\n   <b>world</b> !</div><p>para</p><p>two</p>"),
+            "Hello world !\n\npara\n\ntwo"
+        );
+        assert_eq!(inner_text("<ul><li>one</li><li>two</li></ul>"), "one\ntwo");
+    }
+
+    #[test]
+    fn line_breaks_and_preformatted_text() {
+        assert_eq!(
+            inner_text("<p>a<br>b</p><pre>  x\n  y</pre>"),
+            "a\nb\n\n  x\n  y"
+        );
+    }
+
+    #[test]
+    fn table_cells_are_separated_by_tabs() {
+        assert_eq!(
+            inner_text("<table><tr><td>1</td><td>2</td></tr><tr><th>3</th><td>4</td></tr></table>"),
+            "1\t2\n3\t4"
+        );
+    }
+
+    #[test]
+    fn hidden_and_unrendered_elements_are_skipped() {
+        assert_eq!(
+            inner_text(
+                "<p>shown<span hidden>hidden</span><script>x()</script><style>p{}</style></p>"
+            ),
+            "shown"
+        );
+    }
+}
@@ -0,0 +1,192 @@
+use html5ever::{local_name, namespace_url, ns, QualName};
+
+use crate::node::Node;
+
+/// Returns true for elements whose whitespace is significant.
+pub(crate) fn preserves_whitespace(node: &Node) -> bool {
+    node.as_element()
+        .is_some_and(|e| preserves_whitespace_name(&e.name))
+}
+
+/// Like [`preserves_whitespace`], for an element name.
+pub(crate) fn preserves_whitespace_name(name: &QualName) -> bool {
+    name.ns == ns!(html)
+        && matches!(
+            name.local,
+            local_name!("pre")
+                | local_name!("textarea")
+                | local_name!("listing")
+                | local_name!("plaintext")
+                | local_name!("xmp")
+                | local_name!("script")
+                | local_name!("style")
+        )
+}
+
+/// Returns true for void elements, which have no end tag.
+pub(crate) fn is_void_name(name: &QualName) -> bool {
+    name.ns == ns!(html)
+        && matches!(
+            name.local,
+            local_name!("area")
+                | local_name!("base")
+                | local_name!("basefont")
+                | local_name!("bgsound")
+                | local_name!("br")
+                | local_name!("col")
+                | local_name!("embed")
+                | local_name!("frame")
+                | local_name!("hr")
+                | local_name!("img")
+                | local_name!("input")
+                | local_name!("keygen")
+                | local_name!("link")
+                | local_name!("meta")
+                | local_name!("param")
+                | local_name!("source")
+                | local_name!("track")
+                | local_name!("wbr")
+        )
+}
+
+/// Returns true for elements whose text is written without escaping.
+pub(crate) fn is_raw_text_name(name: &QualName) -> bool {
+    name.ns == ns!(html)
+        && matches!(
+            name.local,
+            local_name!("style")
+                | local_name!("script")
+                | local_name!("xmp")
+                | local_name!("iframe")
+                | local_name!("noembed")
+                | local_name!("noframes")
+                | local_name!("plaintext")
+        )
+}
+
+/// Returns true for elements that are rendered as blocks, or aren't
+/// rendered at all. Unknown elements are treated as inline.
+pub(crate) fn is_block_name(name: &QualName) -> bool {
+    name.ns == ns!(html)
+        && matches!(
+            name.local,
+            local_name!("html")
+                | local_name!("head")
+                | local_name!("body")
+                | local_name!("title")
+                | local_name!("base")
+                | local_name!("link")
+                | local_name!("meta")
+                | local_name!("style")
+                | local_name!("script")
+                | local_name!("noscript")
+                | local_name!("template")
+                | local_name!("address")
+                | local_name!("article")
+                | local_name!("aside")
+                | local_name!("blockquote")
+                | local_name!("center")
+                | local_name!("details")
+                | local_name!("dialog")
+                | local_name!("dd")
+                | local_name!("div")
+                | local_name!("dl")
+                | local_name!("dt")
+                | local_name!("fieldset")
+                | local_name!("figcaption")
+                | local_name!("figure")
+                | local_name!("footer")
+                | local_name!("form")
+                | local_name!("h1")
+                | local_name!("h2")
+                | local_name!("h3")
+                | local_name!("h4")
+                | local_name!("h5")
+                | local_name!("h6")
+                | local_name!("header")
+                | local_name!("hgroup")
+                | local_name!("hr")
+                | local_name!("legend")
+                | local_name!("li")
+                | local_name!("main")
+                | local_name!("menu")
+                | local_name!("nav")
+                | local_name!("ol")
+                | local_name!("optgroup")
+                | local_name!("option")
+                | local_name!("p")
+                | local_name!("pre")
+                | local_name!("section")
+                | local_name!("summary")
+                | local_name!("table")
+                | local_name!("caption")
+                | local_name!("colgroup")
+                | local_name!("col")
+                | local_name!("thead")
+                | local_name!("tbody")
+                | local_name!("tfoot")
+                | local_name!("tr")
+                | local_name!("td")
+                | local_name!("th")
+                | local_name!("ul")
+        )
+}
+
+/// Returns true for elements that are rendered as blocks, so that
+/// whitespace around them is insignificant. Unlike [`is_block_name`], this
+/// is false for `script`, `style`, `template` and the other elements that
+/// aren't rendered: whitespace between them and the text around them still
+/// renders.
+pub(crate) fn is_rendered_block(node: &Node) -> bool {
+    node.as_element()
+        .is_some_and(|e| is_rendered_block_name(&e.name))
+}
+
+/// Like [`is_rendered_block`], for an element name.
+pub(crate) fn is_rendered_block_name(name: &QualName) -> bool {
+    is_block_name(name)
+        && !matches!(
+            name.local,
+            local_name!("title")
+                | local_name!("base")
+                | local_name!("link")
+                | local_name!("meta")
+                | local_name!("style")
+                | local_name!("script")
+                | local_name!("noscript")
+                | local_name!("template")
+        )
+}
+
+/// Replaces each run of ASCII whitespace with a single space, which is how
+/// whitespace in text outside of [`preserves_whitespace`] elements renders.
+pub(crate) fn collapse_whitespace(text: &str) -> String {
+    let mut collapsed = String::with_capacity(text.len());
+    let mut space = false;
+
+    for c in text.chars() {
+        if c.is_ascii_whitespace() {
+            if !space {
+                collapsed.push(' ');
+            }
+            space = true;
+        } else {
+            collapsed.push(c);
+            space = false;
+        }
+    }
+
+    collapsed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::collapse_whitespace;
+
+    #[test]
+    fn collapse_whitespace_keeps_one_space() {
+        assert_eq!(collapse_whitespace(" a \t\n b  "), " a b ");
+        assert_eq!(collapse_whitespace("a\u{a0} b"), "a\u{a0} b");
+        assert_eq!(collapse_whitespace(""), "");
+    }
+}
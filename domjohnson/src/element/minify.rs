@@ -0,0 +1,621 @@
+use std::io::{self, Write};
+
+use html5ever::serialize::{AttrRef, Serialize, Serializer, TraversalScope};
+use html5ever::{local_name, namespace_url, ns, LocalName, QualName};
+
+use super::{
+    kind::{
+        collapse_whitespace, is_raw_text_name, is_rendered_block_name, is_void_name,
+        preserves_whitespace_name,
+    },
+    node_ref::NodeRef,
+};
+
+/// Options for minified serialization.
+#[derive(Debug, Clone)]
+pub struct MinifyOptions {
+    /// Collapse whitespace runs to a single space and drop whitespace
+    /// between block elements. Whitespace inside `pre`, `textarea`, `script`
+    /// and `style` is kept as-is.
+    pub collapse_whitespace: bool,
+
+    /// Drop comments, except for conditional comments.
+    pub remove_comments: bool,
+
+    /// Omit start and end tags where the HTML spec allows it.
+    pub omit_optional_tags: bool,
+
+    /// Leave attribute values unquoted where the syntax allows it.
+    pub unquote_attrs: bool,
+
+    /// Drop attributes set to their default value, like `type="text/css"`
+    /// on `style`.
+    pub remove_default_attrs: bool,
+
+    /// Write boolean attributes like `disabled` without a value.
+    pub shorten_boolean_attrs: bool,
+}
+
+impl Default for MinifyOptions {
+    fn default() -> Self {
+        MinifyOptions {
+            collapse_whitespace: true,
+            remove_comments: true,
+            omit_optional_tags: true,
+            unquote_attrs: true,
+            remove_default_attrs: true,
+            shorten_boolean_attrs: true,
+        }
+    }
+}
+
+/// Writes `node` in minified form.
+pub(crate) fn write_minified<W: Write>(
+    node: NodeRef<'_>,
+    writer: W,
+    traversal_scope: TraversalScope,
+    opts: &MinifyOptions,
+) -> io::Result<()> {
+    let mut minifier = Minifier {
+        writer,
+        opts,
+        stack: vec![Frame::default()],
+        pending: Vec::new(),
+        after_block: true,
+    };
+
+    Serialize::serialize(&node, &mut minifier, traversal_scope)?;
+    minifier.finish()
+}
+
+#[derive(Default)]
+struct Frame {
+    /// The element name if it's an HTML element.
+    name: Option<LocalName>,
+    raw: bool,
+    preserve: bool,
+    void: bool,
+    /// Set for children of void elements, which aren't written.
+    skip: bool,
+}
+
+/// Output held back until the next event shows whether it can be dropped.
+enum Pending {
+    Start(LocalName),
+    End(LocalName),
+    Space { after_block: bool },
+}
+
+/// What follows the pending output.
+#[derive(Clone, Copy)]
+enum Next<'a> {
+    Start(&'a QualName),
+    End(&'a QualName),
+    Text(&'a str),
+    Comment,
+    Other,
+    Eof,
+}
+
+impl<'a> Next<'a> {
+    fn is_start_of(&self, names: &[LocalName]) -> bool {
+        matches!(self, Next::Start(name) if name.ns == ns!(html) && names.contains(&name.local))
+    }
+
+    fn is_parent_end(&self) -> bool {
+        matches!(self, Next::End(_) | Next::Eof)
+    }
+
+    fn starts_with_space(&self) -> bool {
+        matches!(self, Next::Text(text) if text.starts_with(|c: char| c.is_ascii_whitespace()))
+    }
+
+    fn is_block_boundary(&self) -> bool {
+        match self {
+            Next::Start(name) | Next::End(name) => is_rendered_block_name(name),
+            Next::Other | Next::Eof => true,
+            Next::Text(_) | Next::Comment => false,
+        }
+    }
+}
+
+struct Minifier<'o, W: Write> {
+    writer: W,
+    opts: &'o MinifyOptions,
+    stack: Vec<Frame>,
+    pending: Vec<Pending>,
+    /// Whether the last tag written or omitted was a block element's.
+    after_block: bool,
+}
+
+impl<'o, W: Write> Minifier<'o, W> {
+    fn parent(&self) -> &Frame {
+        self.stack.last().expect("root frame")
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.resolve(Next::Eof)
+    }
+
+    /// Writes or drops the pending output now that `next` is known.
+    fn resolve(&mut self, next: Next<'_>) -> io::Result<()> {
+        if let Some(Pending::Space { after_block }) = self.pending.last() {
+            // Whitespace next to a block boundary isn't rendered, and
+            // nothing in `head` is.
+            let in_head = self.parent().name == Some(local_name!("head"));
+            let drop = *after_block || next.is_block_boundary() || in_head;
+            self.pending.pop();
+
+            if !drop {
+                self.resolve_tag(Next::Text(" "))?;
+                return self.writer.write_all(b" ");
+            }
+        }
+
+        self.resolve_tag(next)
+    }
+
+    fn resolve_tag(&mut self, next: Next<'_>) -> io::Result<()> {
+        match self.pending.pop() {
+            Some(Pending::Start(name)) if !can_omit_start(&name, next) => {
+                write!(self.writer, "<{}>", name)
+            }
+            Some(Pending::End(name)) if !can_omit_end(&name, self.parent().name.as_ref(), next) => {
+                write!(self.writer, "</{}>", name)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn write_attr(&mut self, element: &QualName, name: &QualName, value: &str) -> io::Result<()> {
+        if self.opts.remove_default_attrs && is_default_attr(element, name, value) {
+            return Ok(());
+        }
+
+        self.writer.write_all(b" ")?;
+        match name.ns {
+            ns!(xml) => self.writer.write_all(b"xml:")?,
+            ns!(xmlns) if name.local != local_name!("xmlns") => self.writer.write_all(b"xmlns:")?,
+            ns!(xlink) => self.writer.write_all(b"xlink:")?,
+            _ => (),
+        }
+        self.writer.write_all(name.local.as_bytes())?;
+
+        if (self.opts.shorten_boolean_attrs && is_boolean_attr(element, name, value))
+            || (self.opts.unquote_attrs && value.is_empty())
+        {
+            return Ok(());
+        }
+
+        if self.opts.unquote_attrs && can_unquote(value) {
+            self.writer.write_all(b"=")?;
+            write_escaped(&mut self.writer, value, true)
+        } else {
+            self.writer.write_all(b"=\"")?;
+            write_escaped(&mut self.writer, value, true)?;
+            self.writer.write_all(b"\"")
+        }
+    }
+}
+
+impl<'o, W: Write> Serializer for Minifier<'o, W> {
+    fn start_elem<'a, AttrIter>(&mut self, name: QualName, attrs: AttrIter) -> io::Result<()>
+    where
+        AttrIter: Iterator<Item = AttrRef<'a>>,
+    {
+        if self.parent().void || self.parent().skip {
+            self.stack.push(Frame {
+                skip: true,
+                ..Default::default()
+            });
+            return Ok(());
+        }
+
+        self.resolve(Next::Start(&name))?;
+
+        let mut attrs = attrs.peekable();
+        let omittable = name.ns == ns!(html)
+            && matches!(
+                name.local,
+                local_name!("html") | local_name!("head") | local_name!("body")
+            );
+
+        if self.opts.omit_optional_tags && omittable && attrs.peek().is_none() {
+            self.pending.push(Pending::Start(name.local.clone()));
+        } else {
+            write!(self.writer, "<{}", name.local)?;
+            for (attr, value) in attrs {
+                self.write_attr(&name, attr, value)?;
+            }
+            self.writer.write_all(b">")?;
+        }
+
+        self.after_block = is_rendered_block_name(&name);
+        let frame = Frame {
+            name: (name.ns == ns!(html)).then(|| name.local.clone()),
+            raw: is_raw_text_name(&name),
+            preserve: self.parent().preserve || preserves_whitespace_name(&name),
+            void: is_void_name(&name),
+            skip: false,
+        };
+        self.stack.push(frame);
+
+        Ok(())
+    }
+
+    fn end_elem(&mut self, name: QualName) -> io::Result<()> {
+        let frame = self.parent();
+        if frame.skip || frame.void {
+            self.stack.pop();
+            return Ok(());
+        }
+
+        // Resolve while the element is still on the stack, as it's the
+        // parent of whatever is pending.
+        self.resolve(Next::End(&name))?;
+        self.stack.pop();
+        self.after_block = is_rendered_block_name(&name);
+
+        if self.opts.omit_optional_tags && name.ns == ns!(html) {
+            self.pending.push(Pending::End(name.local));
+            Ok(())
+        } else {
+            write!(self.writer, "</{}>", name.local)
+        }
+    }
+
+    fn write_text(&mut self, text: &str) -> io::Result<()> {
+        let parent = self.parent();
+        if parent.void || parent.skip {
+            return Ok(());
+        }
+
+        let raw = parent.raw;
+        if raw || parent.preserve || !self.opts.collapse_whitespace {
+            self.resolve(Next::Text(text))?;
+            self.after_block = false;
+            return if !raw {
+                write_escaped(&mut self.writer, text, false)
+            } else {
+                self.writer.write_all(text.as_bytes())
+            };
+        }
+
+        let collapsed = collapse_whitespace(text);
+        let body = collapsed.trim_matches(' ');
+        let leading = collapsed.starts_with(' ');
+        let trailing = collapsed.ends_with(' ');
+
+        if body.is_empty() {
+            if leading && !matches!(self.pending.last(), Some(Pending::Space { .. })) {
+                self.pending.push(Pending::Space {
+                    after_block: self.after_block,
+                });
+            }
+            return Ok(());
+        }
+
+        if leading && !matches!(self.pending.last(), Some(Pending::Space { .. })) {
+            self.pending.push(Pending::Space {
+                after_block: self.after_block,
+            });
+        }
+
+        self.resolve(Next::Text(body))?;
+        write_escaped(&mut self.writer, body, false)?;
+        self.after_block = false;
+
+        if trailing {
+            self.pending.push(Pending::Space { after_block: false });
+        }
+
+        Ok(())
+    }
+
+    fn write_comment(&mut self, text: &str) -> io::Result<()> {
+        let parent = self.parent();
+        if parent.void || parent.skip || (self.opts.remove_comments && !is_conditional(text)) {
+            return Ok(());
+        }
+
+        self.resolve(Next::Comment)?;
+        write!(self.writer, "<!--{}-->", text)
+    }
+
+    fn write_doctype(&mut self, name: &str) -> io::Result<()> {
+        self.resolve(Next::Other)?;
+        self.after_block = true;
+        write!(self.writer, "<!DOCTYPE {}>", name)
+    }
+
+    fn write_processing_instruction(&mut self, target: &str, data: &str) -> io::Result<()> {
+        self.resolve(Next::Other)?;
+        write!(self.writer, "<?{} {}>", target, data)
+    }
+}
+
+/// The optional start tag rules. Only called for tags without attributes.
+fn can_omit_start(name: &LocalName, next: Next<'_>) -> bool {
+    match *name {
+        local_name!("html") => !matches!(next, Next::Comment),
+        local_name!("head") => matches!(next, Next::Start(_) | Next::End(_)),
+        local_name!("body") => match next {
+            Next::Start(name) => !matches!(
+                name.local,
+                local_name!("meta")
+                    | local_name!("noscript")
+                    | local_name!("link")
+                    | local_name!("script")
+                    | local_name!("style")
+                    | local_name!("template")
+            ),
+            Next::End(_) | Next::Eof => true,
+            Next::Text(_) => !next.starts_with_space(),
+            Next::Comment | Next::Other => false,
+        },
+        _ => false,
+    }
+}
+
+/// The optional end tag rules. `parent` is the enclosing HTML element, if
+/// known.
+fn can_omit_end(name: &LocalName, parent: Option<&LocalName>, next: Next<'_>) -> bool {
+    match *name {
+        local_name!("html") | local_name!("body") => !matches!(next, Next::Comment),
+        local_name!("head") | local_name!("caption") | local_name!("colgroup") => {
+            !matches!(next, Next::Comment) && !next.starts_with_space()
+        }
+        local_name!("li") => next.is_start_of(&[local_name!("li")]) || next.is_parent_end(),
+        local_name!("dt") => next.is_start_of(&[local_name!("dt"), local_name!("dd")]),
+        local_name!("dd") => {
+            next.is_start_of(&[local_name!("dd"), local_name!("dt")]) || next.is_parent_end()
+        }
+        local_name!("rt") | local_name!("rp") => {
+            next.is_start_of(&[local_name!("rt"), local_name!("rp")]) || next.is_parent_end()
+        }
+        local_name!("optgroup") => {
+            next.is_start_of(&[local_name!("optgroup"), local_name!("hr")]) || next.is_parent_end()
+        }
+        local_name!("option") => {
+            next.is_start_of(&[
+                local_name!("option"),
+                local_name!("optgroup"),
+                local_name!("hr"),
+            ]) || next.is_parent_end()
+        }
+        local_name!("thead") => next.is_start_of(&[local_name!("tbody"), local_name!("tfoot")]),
+        local_name!("tbody") => {
+            next.is_start_of(&[local_name!("tbody"), local_name!("tfoot")]) || next.is_parent_end()
+        }
+        local_name!("tfoot") => next.is_parent_end(),
+        local_name!("tr") => next.is_start_of(&[local_name!("tr")]) || next.is_parent_end(),
+        local_name!("td") | local_name!("th") => {
+            next.is_start_of(&[local_name!("td"), local_name!("th")]) || next.is_parent_end()
+        }
+        local_name!("p") => {
+            let closed_by_parent = next.is_parent_end()
+                && parent.is_some_and(|parent| {
+                    !matches!(
+                        *parent,
+                        local_name!("a")
+                            | local_name!("audio")
+                            | local_name!("del")
+                            | local_name!("ins")
+                            | local_name!("map")
+                            | local_name!("noscript")
+                            | local_name!("video")
+                    ) && !parent.contains('-')
+                });
+
+            closed_by_parent
+                || next.is_start_of(&[
+                    local_name!("address"),
+                    local_name!("article"),
+                    local_name!("aside"),
+                    local_name!("blockquote"),
+                    local_name!("details"),
+                    local_name!("dialog"),
+                    local_name!("div"),
+                    local_name!("dl"),
+                    local_name!("fieldset"),
+                    local_name!("figcaption"),
+                    local_name!("figure"),
+                    local_name!("footer"),
+                    local_name!("form"),
+                    local_name!("h1"),
+                    local_name!("h2"),
+                    local_name!("h3"),
+                    local_name!("h4"),
+                    local_name!("h5"),
+                    local_name!("h6"),
+                    local_name!("header"),
+                    local_name!("hgroup"),
+                    local_name!("hr"),
+                    local_name!("main"),
+                    local_name!("menu"),
+                    local_name!("nav"),
+                    local_name!("ol"),
+                    local_name!("p"),
+                    local_name!("pre"),
+                    local_name!("section"),
+                    local_name!("table"),
+                    local_name!("ul"),
+                ])
+        }
+        _ => false,
+    }
+}
+
+/// Returns true for boolean attributes of HTML elements whose value can be
+/// left out, which is when it's empty or the attribute's name. Other values
+/// are kept, as some mean something else, like `hidden="until-found"`.
+fn is_boolean_attr(element: &QualName, name: &QualName, value: &str) -> bool {
+    let boolean = matches!(
+        &*name.local,
+        "allowfullscreen"
+            | "async"
+            | "autofocus"
+            | "autoplay"
+            | "checked"
+            | "controls"
+            | "default"
+            | "defer"
+            | "disabled"
+            | "formnovalidate"
+            | "hidden"
+            | "inert"
+            | "ismap"
+            | "itemscope"
+            | "loop"
+            | "multiple"
+            | "muted"
+            | "nomodule"
+            | "novalidate"
+            | "open"
+            | "playsinline"
+            | "readonly"
+            | "required"
+            | "reversed"
+            | "selected"
+    );
+
+    boolean
+        && element.ns == ns!(html)
+        && name.ns == ns!()
+        && (value.is_empty() || value.eq_ignore_ascii_case(&name.local))
+}
+
+/// Attribute values that are the same as leaving the attribute out.
+fn is_default_attr(element: &QualName, name: &QualName, value: &str) -> bool {
+    if element.ns != ns!(html) || name.ns != ns!() {
+        return false;
+    }
+
+    let value = value.trim();
+    match (&element.local, &name.local) {
+        (&local_name!("script"), &local_name!("type")) => {
+            value.is_empty() || value.eq_ignore_ascii_case("text/javascript")
+        }
+        (&local_name!("script"), &local_name!("language")) => {
+            value.eq_ignore_ascii_case("javascript")
+        }
+        (&local_name!("style"), &local_name!("type"))
+        | (&local_name!("link"), &local_name!("type")) => {
+            value.is_empty() || value.eq_ignore_ascii_case("text/css")
+        }
+        (&local_name!("style"), &local_name!("media"))
+        | (&local_name!("link"), &local_name!("media")) => value.eq_ignore_ascii_case("all"),
+        (&local_name!("form"), &local_name!("method")) => value.eq_ignore_ascii_case("get"),
+        (&local_name!("input"), &local_name!("type")) => value.eq_ignore_ascii_case("text"),
+        (&local_name!("area"), &local_name!("shape")) => value.eq_ignore_ascii_case("rect"),
+        _ => false,
+    }
+}
+
+/// Conditional comments like `<!--[if IE]>...<![endif]-->` carry markup.
+fn is_conditional(comment: &str) -> bool {
+    comment.starts_with("[if") || comment.ends_with("<![endif]")
+}
+
+fn can_unquote(value: &str) -> bool {
+    !value.is_empty()
+        && !value
+            .chars()
+            .any(|c| c.is_ascii_whitespace() || matches!(c, '"' | '\'' | '=' | '<' | '>' | '`'))
+}
+
+fn write_escaped<W: Write>(writer: &mut W, text: &str, attr_mode: bool) -> io::Result<()> {
+    for c in text.chars() {
+        match c {
+            '&' => writer.write_all(b"&amp;"),
+            '\u{00A0}' => writer.write_all(b"&nbsp;"),
+            '"' if attr_mode => writer.write_all(b"&quot;"),
+            '<' if !attr_mode => writer.write_all(b"&lt;"),
+            '>' if !attr_mode => writer.write_all(b"&gt;"),
+            c => write!(writer, "{}", c),
+        }?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MinifyOptions;
+    use crate::Document;
+
+    fn minify(html: &str) -> String {
+        Document::parse(html).minified_html(&MinifyOptions::default())
+    }
+
+    #[test]
+    fn optional_tags_are_omitted() {
+        assert_eq!(
+            minify("<!DOCTYPE html><html><head><title>T</title></head><body><p>a</p><p>b</p></body></html>"),
+            "<!DOCTYPE html><title>T</title><p>a<p>b"
+        );
+        assert_eq!(
+            minify("<table><tr><td>1</td><td>2</td></tr></table>"),
+            "<table><tbody><tr><td>1<td>2</table>"
+        );
+        assert_eq!(
+            minify("<ul>\n  <li>one</li>\n  <li>two</li>\n</ul>"),
+            "<ul><li>one<li>two</ul>"
+        );
+    }
+
+    #[test]
+    fn needed_tags_are_kept() {
+        // A `p` end tag can't be omitted before text, or inside an `a`.
+        assert_eq!(
+            minify("<p>a</p><div>b</div><p>c</p>text"),
+            "<p>a<div>b</div><p>c</p>text"
+        );
+        assert_eq!(minify("<a href=x><p>a</p></a>"), "<a href=x><p>a</p></a>");
+        // A comment as the first child of `body` needs its start tag.
+        let opts = MinifyOptions {
+            remove_comments: false,
+            ..MinifyOptions::default()
+        };
+        assert_eq!(
+            Document::parse("<body><!--x--><p>a</p></body>").minified_html(&opts),
+            "<body><!--x--><p>a"
+        );
+    }
+
+    #[test]
+    fn whitespace_and_comments() {
+        assert_eq!(
+            minify("<!-- c --><!--[if IE]>x<![endif]--><p>a   b\n c</p><pre>  x  </pre>"),
+            "<!--[if IE]>x<![endif]--><p>a b c<pre>  x  </pre>"
+        );
+        assert_eq!(minify("<p>a <b>b</b> c</p>"), "<p>a <b>b</b> c");
+        assert_eq!(
+            minify("<p>a <script>x()</script>b</p>"),
+            "<p>a <script>x()</script>b"
+        );
+        assert_eq!(
+            minify("<head>\n  <meta charset=utf-8>\n  <title>T</title>\n</head>"),
+            "<meta charset=utf-8><title>T</title>"
+        );
+    }
+
+    #[test]
+    fn attributes() {
+        assert_eq!(minify("<input disabled=disabled>"), "<input disabled>");
+        assert_eq!(minify("<div hidden=HIDDEN></div>"), "<div hidden></div>");
+        assert_eq!(
+            minify("<div hidden=until-found></div>"),
+            "<div hidden=until-found></div>"
+        );
+        assert_eq!(
+            minify("<svg><circle disabled=disabled></circle></svg>"),
+            "<svg><circle disabled=disabled></circle></svg>"
+        );
+        assert_eq!(minify("<input value='a b'>"), "<input value=\"a b\">");
+        assert_eq!(minify("<a title=\"x\">q</a>"), "<a title=x>q</a>");
+        assert_eq!(
+            minify("<style type=text/css>a{}</style>"),
+            "<style>a{}</style>"
+        );
+    }
+}
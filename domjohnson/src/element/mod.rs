@@ -1,6 +1,14 @@
+pub(crate) mod canonical;
+mod inner_text;
+pub(crate) mod kind;
+mod minify;
 pub mod node_mut;
 pub mod node_ref;
+mod pretty;
 mod selector;
 pub mod serialize;
+mod xhtml;
 
+pub use self::minify::MinifyOptions;
 pub use self::node_ref::NodeRef;
+pub use self::pretty::{Indent, SerializeOptions};
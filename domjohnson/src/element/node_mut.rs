@@ -1,6 +1,11 @@
 use generational_indextree::{Arena, NodeId};
+use smol_str::SmolStr;
 
-use crate::{node::Node, NodeRef};
+use crate::{
+    document::{free_subtree, is_template, set_inner_html, set_outer_html},
+    node::Node,
+    NodeRef,
+};
 
 use super::node_ref::Text;
 
@@ -23,6 +28,10 @@ impl<'a> core::ops::DerefMut for NodeMut<'a> {
 }
 
 impl<'a> NodeMut<'a> {
+    pub(crate) fn new(tree: &'a mut Arena<Node>, id: NodeId) -> Self {
+        NodeMut { tree, id }
+    }
+
     pub fn html(&self) -> String {
         NodeRef::new(&self.tree, self.id).html()
     }
@@ -32,6 +41,57 @@ impl<'a> NodeMut<'a> {
         NodeRef::new(&self.tree, self.id).inner_html()
     }
 
+    /// Replaces the children of this node with the parsed HTML fragment.
+    pub fn set_inner_html(&mut self, html: &str) {
+        set_inner_html(self.tree, self.id, html)
+    }
+
+    /// Replaces this node with the parsed HTML fragment.
+    pub fn set_outer_html(&mut self, html: &str) {
+        set_outer_html(self.tree, self.id, html)
+    }
+
+    /// Sets the text content of this node. Elements and fragments have their
+    /// children replaced by a single text node, while text, comment and
+    /// processing instruction nodes have their data replaced in place.
+    /// Document and doctype nodes are left untouched.
+    pub fn set_text(&mut self, text: impl Into<SmolStr>) {
+        let text = text.into();
+
+        match self.tree[self.id].get_mut() {
+            Node::Element(_) | Node::Fragment => {}
+            Node::Text(t) => {
+                t.set_text(text);
+                return;
+            }
+            Node::Comment(c) => {
+                c.comment = text;
+                return;
+            }
+            Node::ProcessingInstruction(pi) => {
+                pi.data = text.into();
+                return;
+            }
+            Node::Document | Node::Doctype(_) => return,
+        }
+
+        let target = if is_template(self) {
+            self.tree[self.id].first_child().unwrap_or(self.id)
+        } else {
+            self.id
+        };
+
+        let children = target.children(self.tree).collect::<Vec<_>>();
+        for child in children {
+            free_subtree(self.tree, child);
+        }
+
+        if !text.is_empty() {
+            let child = self.tree.new_node(Node::Text(crate::node::Text { text }));
+            target.append(child, self.tree);
+        }
+    }
+
     pub fn text(&self) -> Text<'_> {
         NodeRef::new(&self.tree, self.id).text()
     }
@@ -48,3 +108,45 @@ impl<'a> NodeMut<'a> {
         self.id.remove_subtree(self.tree)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::Document;
+
+    #[test]
+    fn set_text_replaces_children() {
+        let mut dom = Document::parse("<div><p>a</p><p>b</p></div>");
+        let div = dom.select("div").get(0).unwrap();
+
+        dom.set_text(div, "<x> & y");
+        assert_eq!(dom.inner_html(div), "&lt;x&gt; &amp; y");
+
+        dom.set_text(div, "");
+        assert_eq!(dom.children(div).count(), 0);
+    }
+
+    #[test]
+    fn set_text_changes_character_data_in_place() {
+        let mut dom = Document::parse("<p>a<!--b--></p>");
+        let p = dom.select("p").get(0).unwrap();
+        let text = dom.children(p).next().unwrap();
+        let comment = dom.children(p).nth(1).unwrap();
+
+        dom.set_text(text, "x");
+        dom.set_text(comment, "y");
+        assert_eq!(dom.inner_html(p), "x<!--y-->");
+    }
+
+    #[test]
+    fn set_text_frees_old_children() {
+        let mut dom = Document::parse("<div><p>secret</p></div>");
+        let div = dom.select("div").get(0).unwrap();
+        let p = dom.children(div).next().unwrap();
+        let text = dom.children(p).next().unwrap();
+
+        dom.set_text(div, "b");
+        assert_eq!(dom.orhpans().count(), 0);
+        assert!(dom.get(p).is_none());
+        assert!(dom.get(text).is_none());
+    }
+}
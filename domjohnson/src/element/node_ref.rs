@@ -1,7 +1,14 @@
+use std::io::{self, Write};
 use std::ops::Deref;
 
 use crate::node::{Element, Node};
 
+use super::canonical::write_canonical;
+use super::inner_text::inner_text;
+use super::minify::{write_minified, MinifyOptions};
+use super::pretty::{write_pretty, SerializeOptions};
+use super::xhtml::write_xhtml;
+
 use generational_indextree::{Arena, NodeEdge, NodeId};
 use html5ever::serialize::{serialize, SerializeOpts, TraversalScope};
 use smol_str::SmolStr;
@@ -60,14 +67,24 @@ impl<'a> NodeRef<'a> {
     }
 
     fn serialize(&self, traversal_scope: TraversalScope) -> String {
+        let mut buf = Vec::new();
+        self.write_html(&mut buf, traversal_scope).unwrap();
+        String::from_utf8(buf).unwrap()
+    }
+
+    /// Writes the HTML of this node, or of its children with
+    /// [`TraversalScope::ChildrenOnly`], without buffering the output.
+    pub fn write_html<W: Write>(
+        &self,
+        writer: &mut W,
+        traversal_scope: TraversalScope,
+    ) -> io::Result<()> {
         let opts = SerializeOpts {
             scripting_enabled: false, // It's not clear what this does.
             traversal_scope,
             create_missing_parent: false,
         };
-        let mut buf = Vec::new();
-        serialize(&mut buf, self, opts).unwrap();
-        String::from_utf8(buf).unwrap()
+        serialize(writer, self, opts)
     }
 
     pub fn node(&self) -> &'a Node {
@@ -102,6 +119,59 @@ impl<'a> NodeRef<'a> {
         self.serialize(TraversalScope::ChildrenOnly(None))
     }
 
+    /// Returns the HTML of this node, indented for readability.
+    pub fn pretty_html(&self, opts: &SerializeOptions) -> String {
+        let mut buf = Vec::new();
+        write_pretty(*self, &mut buf, opts).unwrap();
+        String::from_utf8(buf).unwrap()
+    }
+
+    /// Returns the minified HTML of this node.
+    pub fn minified_html(&self, opts: &MinifyOptions) -> String {
+        let mut buf = Vec::new();
+        write_minified(*self, &mut buf, TraversalScope::IncludeNode, opts).unwrap();
+        String::from_utf8(buf).unwrap()
+    }
+
+    /// Returns the HTML of this node in canonical form.
+    pub fn canonical_html(&self) -> String {
+        let mut buf = Vec::new();
+        self.write_canonical(&mut buf, TraversalScope::IncludeNode)
+            .unwrap();
+        String::from_utf8(buf).unwrap()
+    }
+
+    /// Writes this node in canonical form: attributes are sorted and
+    /// quoting, character references and insignificant whitespace are
+    /// normalized, so equivalent markup always serializes the same.
+    pub fn write_canonical<W: Write>(
+        &self,
+        writer: &mut W,
+        traversal_scope: TraversalScope,
+    ) -> io::Result<()> {
+        write_canonical(*self, writer, traversal_scope)
+    }
+
+    /// Returns this node serialized as well-formed XHTML.
+    pub fn xhtml(&self) -> String {
+        let mut buf = Vec::new();
+        self.write_xhtml(&mut buf, TraversalScope::IncludeNode)
+            .unwrap();
+        String::from_utf8(buf).unwrap()
+    }
+
+    /// Writes this node as well-formed XHTML: void elements are
+    /// self-closing, `svg` and `math` get namespace declarations and
+    /// `script` and `style` contents are wrapped in CDATA sections where
+    /// needed.
+    pub fn write_xhtml<W: Write>(
+        &self,
+        writer: &mut W,
+        traversal_scope: TraversalScope,
+    ) -> io::Result<()> {
+        write_xhtml(*self, writer, traversal_scope)
+    }
+
     /// Returns an iterator over descendent text nodes.
     pub fn text(&self) -> Text<'a> {
         Text {
@@ -109,6 +179,14 @@ impl<'a> NodeRef<'a> {
         }
     }
 
+    /// Returns the text as it would be rendered: hidden elements, scripts
+    /// and styles are skipped, block elements and `<br>` start new lines,
+    /// table cells are separated by tabs and whitespace is collapsed
+    /// outside `pre`.
+    pub fn inner_text(&self) -> String {
+        inner_text(*self)
+    }
+
     pub fn attr(&self, str: impl AsRef<str>) -> Option<&String> {
         if let Some(element) = self.as_element() {
             element.attr(str.as_ref())
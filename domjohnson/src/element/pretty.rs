@@ -0,0 +1,462 @@
+use std::io::{self, Write};
+
+use html5ever::serialize::{HtmlSerializer, Serialize, SerializeOpts, Serializer, TraversalScope};
+use html5ever::{expanded_name, local_name, namespace_url, ns};
+
+use crate::node::Node;
+
+use super::{
+    kind::{
+        is_block_name, is_raw_text_name, is_rendered_block, is_void_name, preserves_whitespace,
+        preserves_whitespace_name,
+    },
+    node_ref::NodeRef,
+};
+
+/// Indentation used for each nesting level.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Indent {
+    /// Indent with the given number of spaces.
+    Spaces(usize),
+    /// Indent with one tab.
+    Tabs,
+}
+
+/// Options for pretty-printed serialization.
+#[derive(Debug, Clone)]
+pub struct SerializeOptions {
+    /// Indentation for each nesting level.
+    pub indent: Indent,
+
+    /// Block elements that fit within this many columns, indentation
+    /// included, are kept on one line, and text is wrapped at this width.
+    /// `None` puts the children of every block element with block children
+    /// on their own lines, without wrapping text. Tabs count as 4 columns.
+    pub max_width: Option<usize>,
+}
+
+impl Default for SerializeOptions {
+    fn default() -> Self {
+        SerializeOptions {
+            indent: Indent::Spaces(2),
+            max_width: Some(80),
+        }
+    }
+}
+
+/// Writes `node` with one block element per line.
+///
+/// Only insignificant whitespace is changed: the whitespace next to block
+/// elements and at their edges, and runs of whitespace in text, which can
+/// be broken across lines. `pre`, `textarea`, `script` and `style` are
+/// written as-is.
+pub(crate) fn write_pretty<W: Write>(
+    node: NodeRef<'_>,
+    writer: W,
+    opts: &SerializeOptions,
+) -> io::Result<()> {
+    let mut printer = Printer {
+        serializer: HtmlSerializer::new(writer, serialize_opts()),
+        opts,
+    };
+
+    match node.node() {
+        Node::Document | Node::Fragment => printer.children(node, 0),
+        _ => printer.node(node, 0),
+    }
+}
+
+struct Printer<'o, W: Write> {
+    serializer: HtmlSerializer<W>,
+    opts: &'o SerializeOptions,
+}
+
+impl<'o, W: Write> Printer<'o, W> {
+    fn node(&mut self, node: NodeRef<'_>, depth: usize) -> io::Result<()> {
+        if is_blank(&node) {
+            return Ok(());
+        }
+
+        if !is_container(node) || self.fits(node, depth) {
+            self.indent(depth)?;
+            flat(&mut self.serializer, node)?;
+            return self.serializer.writer.write_all(b"\n");
+        }
+
+        let element = node.as_element().expect("containers are elements");
+        let attrs = element.attrs.iter().map(|(k, v)| (k, &v[..]));
+
+        self.indent(depth)?;
+        self.serializer.start_elem(element.name.clone(), attrs)?;
+        self.serializer.writer.write_all(b"\n")?;
+
+        // Block children go on their own lines, the inline content between
+        // them is wrapped.
+        let mut run = Vec::new();
+        for child in content(node).children() {
+            if breaks(&child) {
+                self.inline(&run, depth + 1)?;
+                run.clear();
+                self.node(child, depth + 1)?;
+            } else {
+                run.push(child);
+            }
+        }
+        self.inline(&run, depth + 1)?;
+
+        self.indent(depth)?;
+        self.serializer.end_elem(element.name.clone())?;
+        self.serializer.writer.write_all(b"\n")
+    }
+
+    fn children(&mut self, node: NodeRef<'_>, depth: usize) -> io::Result<()> {
+        for child in node.children() {
+            self.node(child, depth)?;
+        }
+        Ok(())
+    }
+
+    /// Writes a run of text and inline elements, breaking lines at
+    /// whitespace to keep within `max_width` where possible.
+    fn inline(&mut self, nodes: &[NodeRef<'_>], depth: usize) -> io::Result<()> {
+        let mut words = Words {
+            serializer: HtmlSerializer::new(Vec::new(), serialize_opts()),
+            words: Vec::new(),
+            space: false,
+        };
+        for node in nodes {
+            words.node(*node)?;
+        }
+        if words.words.is_empty() {
+            return Ok(());
+        }
+
+        let start = depth * self.indent_width();
+        let mut width = start;
+        self.indent(depth)?;
+
+        for (idx, word) in words.words.iter().enumerate() {
+            let len = word.html.chars().count();
+            if idx > 0 && word.space {
+                match self.opts.max_width {
+                    Some(max) if width > start && width + 1 + len > max => {
+                        self.serializer.writer.write_all(b"\n")?;
+                        self.indent(depth)?;
+                        width = start;
+                    }
+                    _ => {
+                        self.serializer.writer.write_all(b" ")?;
+                        width += 1;
+                    }
+                }
+            }
+            self.serializer.writer.write_all(word.html.as_bytes())?;
+            width += len;
+        }
+
+        self.serializer.writer.write_all(b"\n")
+    }
+
+    fn indent_width(&self) -> usize {
+        match self.opts.indent {
+            Indent::Spaces(n) => n,
+            Indent::Tabs => 4,
+        }
+    }
+
+    fn indent(&mut self, depth: usize) -> io::Result<()> {
+        for _ in 0..depth {
+            match self.opts.indent {
+                Indent::Spaces(n) => write!(self.serializer.writer, "{:n$}", "")?,
+                Indent::Tabs => self.serializer.writer.write_all(b"\t")?,
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns true if `node` fits on the rest of the line.
+    fn fits(&self, node: NodeRef<'_>, depth: usize) -> bool {
+        let Some(max_width) = self.opts.max_width else {
+            // Without a width, only elements with block children are broken.
+            return !content(node).children().any(|child| breaks(&child));
+        };
+
+        let Some(remaining) = max_width.checked_sub(depth * self.indent_width()) else {
+            return false;
+        };
+
+        let writer = LineWriter {
+            len: 0,
+            max: remaining,
+        };
+        let mut serializer = HtmlSerializer::new(writer, serialize_opts());
+        flat(&mut serializer, node).is_ok()
+    }
+}
+
+/// Writes `node` on a single line, dropping the whitespace-only text at the
+/// edges of block elements and next to the children that [`breaks`] lines.
+fn flat<S: Serializer>(serializer: &mut S, node: NodeRef<'_>) -> io::Result<()> {
+    if !is_container(node) {
+        return Serialize::serialize(&node, serializer, TraversalScope::IncludeNode);
+    }
+
+    let element = node.as_element().expect("containers are elements");
+    let attrs = element.attrs.iter().map(|(k, v)| (k, &v[..]));
+    serializer.start_elem(element.name.clone(), attrs)?;
+
+    let children = content(node).children().collect::<Vec<_>>();
+    for (idx, child) in children.iter().enumerate() {
+        if is_blank(child) {
+            let prev = idx.checked_sub(1).map(|idx| &children[idx]);
+            let next = children.get(idx + 1);
+            if [prev, next].iter().any(|n| n.is_none_or(|n| breaks(n))) {
+                continue;
+            }
+        }
+        flat(serializer, *child)?;
+    }
+
+    serializer.end_elem(element.name.clone())
+}
+
+/// Returns true for block elements whose children may be put on their own
+/// lines, which is all of them except those that keep their whitespace.
+fn is_container(node: NodeRef<'_>) -> bool {
+    is_rendered_block(&node)
+        && !preserves_whitespace(&node)
+        && content(node).children().next().is_some()
+}
+
+/// Returns true for nodes that go on their own lines: rendered blocks, and
+/// the children of `head`, none of which are rendered. Other elements, like
+/// a `script` between two words, stay inline, since breaking the line
+/// would add a space.
+fn breaks(node: &NodeRef<'_>) -> bool {
+    is_rendered_block(node)
+        || (node.is_element()
+            && node.parent().is_some_and(|parent| {
+                parent
+                    .as_element()
+                    .is_some_and(|e| e.name.expanded() == expanded_name!(html "head"))
+            }))
+}
+
+/// Returns true for text that is only ASCII whitespace, which isn't
+/// significant next to a block. Other whitespace, like U+00A0, renders.
+fn is_blank(node: &NodeRef<'_>) -> bool {
+    node.as_text()
+        .is_some_and(|text| text.chars().all(|c| c.is_ascii_whitespace()))
+}
+
+/// A unit of inline content that is never broken, with whether it follows
+/// whitespace, where a line may be broken instead.
+struct Word {
+    html: String,
+    space: bool,
+}
+
+/// Splits inline content into words: the words of text, and the tags of
+/// inline elements, which are glued to the text inside them.
+struct Words {
+    serializer: HtmlSerializer<Vec<u8>>,
+    words: Vec<Word>,
+    space: bool,
+}
+
+impl Words {
+    fn node(&mut self, node: NodeRef<'_>) -> io::Result<()> {
+        match node.node() {
+            Node::Text(text) => {
+                if text.starts_with(|c: char| c.is_ascii_whitespace()) {
+                    self.space = true;
+                }
+                for (idx, word) in text.split_ascii_whitespace().enumerate() {
+                    if idx > 0 {
+                        self.space = true;
+                    }
+                    self.serializer.write_text(word)?;
+                    self.push();
+                }
+                if text.ends_with(|c: char| c.is_ascii_whitespace()) {
+                    self.space = true;
+                }
+            }
+            Node::Element(element) if is_breakable_inline(node) => {
+                let attrs = element.attrs.iter().map(|(k, v)| (k, &v[..]));
+                self.serializer.start_elem(element.name.clone(), attrs)?;
+                self.push();
+                for child in node.children() {
+                    self.node(child)?;
+                }
+                self.serializer.end_elem(element.name.clone())?;
+                self.push();
+            }
+            _ => {
+                flat(&mut self.serializer, node)?;
+                self.push();
+            }
+        }
+        Ok(())
+    }
+
+    /// Turns what was serialized since the last word into a word.
+    fn push(&mut self) {
+        let html = std::mem::take(&mut self.serializer.writer);
+        self.words.push(Word {
+            html: String::from_utf8(html).expect("serializer writes utf-8"),
+            space: std::mem::take(&mut self.space),
+        });
+    }
+}
+
+/// Returns true for inline HTML elements whose whitespace may be broken:
+/// not void, blocks, templates or elements that keep their whitespace.
+fn is_breakable_inline(node: NodeRef<'_>) -> bool {
+    node.as_element().is_some_and(|e| {
+        e.name.ns == ns!(html)
+            && !is_block_name(&e.name)
+            && !is_void_name(&e.name)
+            && !preserves_whitespace_name(&e.name)
+            && !is_raw_text_name(&e.name)
+    })
+}
+
+/// The node holding an element's children, which for templates is the
+/// contents fragment.
+fn content(node: NodeRef<'_>) -> NodeRef<'_> {
+    if crate::document::is_template(&node) {
+        if let Some(fragment) = node.children().find(|c| c.is_fragment()) {
+            return fragment;
+        }
+    }
+    node
+}
+
+fn serialize_opts() -> SerializeOpts {
+    SerializeOpts {
+        scripting_enabled: false,
+        traversal_scope: TraversalScope::IncludeNode,
+        create_missing_parent: false,
+    }
+}
+
+/// A writer that fails once its input exceeds `max` bytes or contains a
+/// line break, used to measure whether a node fits on a line.
+struct LineWriter {
+    len: usize,
+    max: usize,
+}
+
+impl Write for LineWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.len += buf.len();
+        if self.len > self.max || buf.contains(&b'\n') {
+            return Err(io::ErrorKind::WriteZero.into());
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Indent, NodeRef, SerializeOptions};
+    use crate::Document;
+
+    fn pretty(html: &str, max_width: Option<usize>) -> String {
+        let dom = Document::parse(html);
+        let node = dom.select("body > *").get(0).unwrap();
+        NodeRef::new(dom.tree(), node).pretty_html(&SerializeOptions {
+            indent: Indent::Spaces(2),
+            max_width,
+        })
+    }
+
+    #[test]
+    fn blocks_go_on_their_own_lines() {
+        let html = "<div>\n <p>a</p>  <p>b</p>\n</div>";
+        assert_eq!(pretty(html, Some(80)), "<div><p>a</p><p>b</p></div>\n");
+        assert_eq!(
+            pretty(html, Some(10)),
+            "<div>\n  <p>a</p>\n  <p>b</p>\n</div>\n"
+        );
+        assert_eq!(
+            pretty(html, None),
+            "<div>\n  <p>a</p>\n  <p>b</p>\n</div>\n"
+        );
+    }
+
+    #[test]
+    fn mixed_content_breaks_at_blocks() {
+        let html = "<div>Name: <input name=x> <b>bold</b><div>block</div>tail</div>";
+        assert_eq!(
+            pretty(html, None),
+            "<div>\n  Name: <input name=\"x\"> <b>bold</b>\n  <div>block</div>\n  tail\n</div>\n"
+        );
+    }
+
+    #[test]
+    fn inline_runs_wrap_at_whitespace() {
+        let html = "<p>one two three four five six seven eight</p>";
+        assert_eq!(
+            pretty(html, Some(20)),
+            "<p>\n  one two three four\n  five six seven\n  eight\n</p>\n"
+        );
+
+        // Tags stay glued to the text they touch.
+        let html = "<p>one <em>two three</em>, four</p>";
+        assert_eq!(
+            pretty(html, Some(12)),
+            "<p>\n  one <em>two\n  three</em>,\n  four\n</p>\n"
+        );
+    }
+
+    #[test]
+    fn non_breaking_spaces_are_kept() {
+        assert_eq!(
+            pretty("<div><p>a</p>&nbsp;<p>b</p></div>", None),
+            "<div>\n  <p>a</p>\n  &nbsp;\n  <p>b</p>\n</div>\n"
+        );
+    }
+
+    #[test]
+    fn unrendered_elements_stay_inline() {
+        assert_eq!(
+            pretty("<div>a<script>x()</script>b<div>c</div></div>", None),
+            "<div>\n  a<script>x()</script>b\n  <div>c</div>\n</div>\n"
+        );
+        assert_eq!(
+            pretty(
+                "<div><template>\n <p>t</p> </template><div>c</div></div>",
+                None
+            ),
+            "<div>\n  <template>\n <p>t</p> </template>\n  <div>c</div>\n</div>\n"
+        );
+    }
+
+    #[test]
+    fn head_children_go_on_their_own_lines() {
+        let dom = Document::parse("<head><meta charset=utf-8><title>T</title></head>");
+        let head = dom.select("head").get(0).unwrap();
+        assert_eq!(
+            NodeRef::new(dom.tree(), head).pretty_html(&SerializeOptions {
+                indent: Indent::Spaces(2),
+                max_width: None,
+            }),
+            "<head>\n  <meta charset=\"utf-8\">\n  <title>T</title>\n</head>\n"
+        );
+    }
+
+    #[test]
+    fn preserved_whitespace_is_kept() {
+        let html = "<div><pre>  a\n b</pre>x &amp; y</div>";
+        assert_eq!(
+            pretty(html, Some(5)),
+            "<div>\n  <pre>  a\n b</pre>\n  x\n  &amp;\n  y\n</div>\n"
+        );
+    }
+}
@@ -2,7 +2,7 @@ use std::io::Error;
 
 use html5ever::serialize::{Serialize, Serializer, TraversalScope};
 
-use crate::node::Node;
+use crate::node::{Doctype, Node};
 
 use super::{node_ref::Edge, NodeRef};
 
@@ -15,13 +15,13 @@ impl<'a> Serialize for NodeRef<'a> {
         for edge in self.traverse() {
             match edge {
                 Edge::Open(node) => {
-                    if node == *self && traversal_scope == TraversalScope::ChildrenOnly(None) {
+                    if node == *self && matches!(traversal_scope, TraversalScope::ChildrenOnly(_)) {
                         continue;
                     }
 
                     match node.node() {
                         Node::Doctype(ref doctype) => {
-                            serializer.write_doctype(doctype.name())?;
+                            serializer.write_doctype(&doctype_body(doctype))?;
                         }
                         Node::Comment(ref comment) => {
                             serializer.write_comment(comment)?;
@@ -33,12 +33,17 @@ impl<'a> Serialize for NodeRef<'a> {
                             let attrs = elem.attrs.iter().map(|(k, v)| (k, &v[..]));
                             serializer.start_elem(elem.name.clone(), attrs)?;
                         }
-                        _ => (),
+                        Node::ProcessingInstruction(ref pi) => {
+                            serializer.write_processing_instruction(&pi.target, &pi.data)?;
+                        }
+                        // Documents and fragments, including template
+                        // contents, only contribute their children.
+                        Node::Document | Node::Fragment => (),
                     }
                 }
 
                 Edge::Close(node) => {
-                    if node == *self && traversal_scope == TraversalScope::ChildrenOnly(None) {
+                    if node == *self && matches!(traversal_scope, TraversalScope::ChildrenOnly(_)) {
                         continue;
                     }
 
@@ -52,3 +57,79 @@ impl<'a> Serialize for NodeRef<'a> {
         Ok(())
     }
 }
+
+/// Returns what follows `<!DOCTYPE ` in a doctype declaration, keeping the
+/// public and system identifiers of legacy doctypes.
+pub(crate) fn doctype_body(doctype: &Doctype) -> String {
+    let mut body = doctype.name().to_string();
+    if !doctype.public_id().is_empty() {
+        body.push_str(" PUBLIC ");
+        push_quoted(&mut body, doctype.public_id());
+        if !doctype.system_id().is_empty() {
+            body.push(' ');
+            push_quoted(&mut body, doctype.system_id());
+        }
+    } else if !doctype.system_id().is_empty() {
+        body.push_str(" SYSTEM ");
+        push_quoted(&mut body, doctype.system_id());
+    }
+    body
+}
+
+/// Quotes a doctype identifier, with `'` if it contains `"`. An identifier
+/// can't contain both, as either ends it.
+fn push_quoted(body: &mut String, id: &str) {
+    let quote = if id.contains('"') { '\'' } else { '"' };
+    body.push(quote);
+    body.push_str(id);
+    body.push(quote);
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::node::{Node, ProcessingInstruction};
+    use crate::Document;
+
+    fn round_trip(html: &str) {
+        let dom = Document::parse(html);
+        assert_eq!(dom.to_string(), html);
+        assert_eq!(Document::parse(&dom.to_string()).to_string(), html);
+    }
+
+    #[test]
+    fn doctype_ids_round_trip() {
+        round_trip("<!DOCTYPE html><html><head></head><body></body></html>");
+        round_trip(
+            "<!DOCTYPE html PUBLIC \"-//W3C//DTD HTML 4.01//EN\" \
+             \"http://www.w3.org/TR/html4/strict.dtd\"><html><head></head><body></body></html>",
+        );
+        round_trip(
+            "<!DOCTYPE html SYSTEM \"about:legacy-compat\"><html><head></head><body></body></html>",
+        );
+        round_trip(
+            "<!DOCTYPE html PUBLIC 'say \"hi\"' \"x\"><html><head></head><body></body></html>",
+        );
+    }
+
+    #[test]
+    fn template_contents_round_trip() {
+        round_trip(
+            "<html><head><template><p>a</p><template>b</template></template></head>\
+             <body></body></html>",
+        );
+    }
+
+    #[test]
+    fn processing_instruction_data_is_unchanged() {
+        let mut dom = Document::parse("<p></p>");
+        let p = dom.select("p").get(0).unwrap();
+        let pi = dom.create_comment("");
+        dom[pi] = Node::ProcessingInstruction(ProcessingInstruction {
+            target: "php".to_string(),
+            data: "echo 1;".to_string(),
+        });
+        dom.append(p, pi);
+
+        assert_eq!(dom.inner_html(p), "<?php echo 1;>");
+    }
+}
@@ -0,0 +1,335 @@
+use std::io::{self, Write};
+
+use html5ever::serialize::{AttrRef, Serialize, Serializer, TraversalScope};
+use html5ever::{expanded_name, local_name, namespace_url, ns, Namespace, Prefix, QualName};
+
+use super::{kind::is_void_name, node_ref::NodeRef};
+
+/// Writes `node` as well-formed XML, following the XHTML syntax.
+pub(crate) fn write_xhtml<W: Write>(
+    node: NodeRef<'_>,
+    writer: W,
+    traversal_scope: TraversalScope,
+) -> io::Result<()> {
+    let mut serializer = XhtmlSerializer {
+        writer,
+        stack: vec![Frame::default()],
+    };
+    Serialize::serialize(&node, &mut serializer, traversal_scope)
+}
+
+#[derive(Default)]
+struct Frame {
+    /// The default namespace in scope.
+    ns: Namespace,
+    /// Prefixes declared on this element.
+    prefixes: Vec<(Prefix, Namespace)>,
+    cdata: Option<Cdata>,
+    /// Set for void elements and their children, which aren't written.
+    skip: bool,
+}
+
+/// How text is wrapped in a CDATA section, commented out so that the
+/// output still works when parsed as HTML.
+#[derive(Clone, Copy)]
+enum Cdata {
+    Script,
+    Style,
+}
+
+struct XhtmlSerializer<W: Write> {
+    writer: W,
+    stack: Vec<Frame>,
+}
+
+impl<W: Write> XhtmlSerializer<W> {
+    fn parent(&self) -> &Frame {
+        self.stack.last().expect("no parent frame")
+    }
+
+    fn prefix_ns(&self, prefix: &Prefix) -> Option<&Namespace> {
+        self.stack.iter().rev().find_map(|frame| {
+            frame
+                .prefixes
+                .iter()
+                .find(|(p, _)| p == prefix)
+                .map(|(_, ns)| ns)
+        })
+    }
+}
+
+impl<W: Write> Serializer for XhtmlSerializer<W> {
+    fn start_elem<'a, AttrIter>(&mut self, name: QualName, attrs: AttrIter) -> io::Result<()>
+    where
+        AttrIter: Iterator<Item = AttrRef<'a>>,
+    {
+        if self.parent().skip {
+            self.stack.push(Frame {
+                skip: true,
+                ..Default::default()
+            });
+            return Ok(());
+        }
+
+        let mut frame = Frame {
+            ns: name.ns.clone(),
+            cdata: match name.expanded() {
+                expanded_name!(html "script") => Some(Cdata::Script),
+                expanded_name!(html "style") => Some(Cdata::Style),
+                _ => None,
+            },
+            skip: is_void_name(&name),
+            prefixes: Vec::new(),
+        };
+
+        write!(self.writer, "<{}", name.local)?;
+        if name.ns != self.parent().ns {
+            self.writer.write_all(b" xmlns=\"")?;
+            write_escaped(&mut self.writer, &name.ns, true)?;
+            self.writer.write_all(b"\"")?;
+        }
+
+        for (attr, value) in attrs {
+            // Declarations are derived from the element and attribute
+            // names, so any in the source are dropped.
+            if attr.ns == ns!(xmlns) || attr.local == local_name!("xmlns") {
+                continue;
+            }
+            // HTML elements keep `xml:lang` and the like as plain names,
+            // which are written in the XML namespace they stand for.
+            let (xml, local) = match attr.local.strip_prefix("xml:") {
+                Some(local) if attr.ns == ns!() => (true, local),
+                _ => (attr.ns == ns!(xml), &*attr.local),
+            };
+            if !is_xml_name(local) {
+                continue;
+            }
+
+            self.writer.write_all(b" ")?;
+            if xml {
+                self.writer.write_all(b"xml:")?;
+            } else if attr.ns != ns!() {
+                let prefix = attr_prefix(attr);
+                let declared = frame
+                    .prefixes
+                    .iter()
+                    .find(|(p, _)| *p == prefix)
+                    .map(|(_, ns)| ns)
+                    .or_else(|| self.prefix_ns(&prefix));
+
+                if declared != Some(&attr.ns) {
+                    write!(self.writer, "xmlns:{}=\"", prefix)?;
+                    write_escaped(&mut self.writer, &attr.ns, true)?;
+                    self.writer.write_all(b"\" ")?;
+                    frame.prefixes.push((prefix.clone(), attr.ns.clone()));
+                }
+                write!(self.writer, "{}:", prefix)?;
+            }
+
+            write!(self.writer, "{}=\"", local)?;
+            write_escaped(&mut self.writer, value, true)?;
+            self.writer.write_all(b"\"")?;
+        }
+
+        let void = frame.skip;
+        self.stack.push(frame);
+
+        if void {
+            self.writer.write_all(b" />")
+        } else {
+            self.writer.write_all(b">")
+        }
+    }
+
+    fn end_elem(&mut self, name: QualName) -> io::Result<()> {
+        let frame = self.stack.pop().expect("no parent frame");
+        if frame.skip {
+            return Ok(());
+        }
+        write!(self.writer, "</{}>", name.local)
+    }
+
+    fn write_text(&mut self, text: &str) -> io::Result<()> {
+        let parent = self.parent();
+        if parent.skip {
+            return Ok(());
+        }
+
+        // Script and style text is always in a CDATA section, as escaping
+        // `>` or `&` would change it when the output is parsed as HTML.
+        let cdata = parent.cdata;
+        match cdata {
+            Some(kind) if !text.is_empty() => {
+                let (open, close) = match kind {
+                    Cdata::Script => ("//<![CDATA[\n", "\n//]]>"),
+                    Cdata::Style => ("/*<![CDATA[*/\n", "\n/*]]>*/"),
+                };
+                self.writer.write_all(open.as_bytes())?;
+                // Split `]]>` across two sections, with the markers in
+                // comments, which both languages skip as HTML text.
+                let text = xml_chars(text).replace("]]>", "]]/*]]>*//*<![CDATA[*/>");
+                self.writer.write_all(text.as_bytes())?;
+                self.writer.write_all(close.as_bytes())
+            }
+            _ => write_escaped(&mut self.writer, text, false),
+        }
+    }
+
+    fn write_comment(&mut self, text: &str) -> io::Result<()> {
+        if self.parent().skip {
+            return Ok(());
+        }
+
+        // `--` isn't allowed in XML comments, nor is a trailing `-`.
+        let mut text = xml_chars(text);
+        while text.contains("--") {
+            text = text.replace("--", "- -");
+        }
+        if text.ends_with('-') {
+            text.push(' ');
+        }
+        write!(self.writer, "<!--{}-->", text)
+    }
+
+    fn write_doctype(&mut self, name: &str) -> io::Result<()> {
+        write!(self.writer, "<!DOCTYPE {}>", name)
+    }
+
+    fn write_processing_instruction(&mut self, target: &str, data: &str) -> io::Result<()> {
+        if data.is_empty() {
+            write!(self.writer, "<?{}?>", target)
+        } else {
+            write!(self.writer, "<?{} {}?>", target, data)
+        }
+    }
+}
+
+/// The prefix for a namespaced attribute, which keeps the one it was
+/// parsed with.
+fn attr_prefix(attr: &QualName) -> Prefix {
+    match attr.prefix {
+        Some(ref prefix) => prefix.clone(),
+        None if attr.ns == ns!(xlink) => Prefix::from("xlink"),
+        None => Prefix::from("ns"),
+    }
+}
+
+/// Returns true if `name` can be written as an XML attribute name. HTML
+/// allows names like `@click` or `v-on:click` that XML doesn't, and those
+/// are dropped: there's no escaping for names, and a colon would need a
+/// namespace that the attribute doesn't have.
+fn is_xml_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    let start = |c: char| c.is_alphabetic() || c == '_';
+    chars.next().is_some_and(start)
+        && chars.all(|c| start(c) || c.is_numeric() || matches!(c, '-' | '.' | '\u{b7}'))
+}
+
+/// Replaces characters that can't appear in an XML document.
+fn xml_chars(text: &str) -> String {
+    text.chars()
+        .map(|c| match c {
+            '\t' | '\n' | '\r' => c,
+            '\u{0}'..='\u{1f}' | '\u{fffe}' | '\u{ffff}' => '\u{fffd}',
+            _ => c,
+        })
+        .collect()
+}
+
+fn write_escaped<W: Write>(writer: &mut W, text: &str, attr_mode: bool) -> io::Result<()> {
+    for c in xml_chars(text).chars() {
+        match c {
+            '&' => writer.write_all(b"&amp;")?,
+            '<' => writer.write_all(b"&lt;")?,
+            '>' => writer.write_all(b"&gt;")?,
+            '"' if attr_mode => writer.write_all(b"&quot;")?,
+            '\t' if attr_mode => writer.write_all(b"&#9;")?,
+            '\n' if attr_mode => writer.write_all(b"&#10;")?,
+            '\r' => writer.write_all(b"&#13;")?,
+            c => write!(writer, "{}", c)?,
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::node::{Node, ProcessingInstruction};
+    use crate::Document;
+
+    fn xhtml(html: &str) -> String {
+        let dom = Document::parse(html);
+        let node = dom.select("body > *").get(0).unwrap();
+        dom.node_ref(node).xhtml()
+    }
+
+    #[test]
+    fn xml_attributes_keep_their_prefix() {
+        assert_eq!(
+            xhtml("<p xml:lang=fr>a</p>"),
+            "<p xmlns=\"http://www.w3.org/1999/xhtml\" xml:lang=\"fr\">a</p>"
+        );
+        assert_eq!(
+            xhtml("<svg xml:space=preserve></svg>"),
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" xml:space=\"preserve\"></svg>"
+        );
+    }
+
+    #[test]
+    fn names_xml_cant_represent_are_dropped() {
+        assert_eq!(
+            xhtml("<p @click=a v-on:click=b>a</p>"),
+            "<p xmlns=\"http://www.w3.org/1999/xhtml\">a</p>"
+        );
+    }
+
+    #[test]
+    fn scripts_and_styles_are_in_cdata() {
+        assert_eq!(
+            xhtml("<body><script>if (a > b) f()</script>"),
+            "<script xmlns=\"http://www.w3.org/1999/xhtml\">//<![CDATA[\nif (a > b) f()\n//]]></script>"
+        );
+        assert_eq!(
+            xhtml("<body><style>a > b {}</style>"),
+            "<style xmlns=\"http://www.w3.org/1999/xhtml\">/*<![CDATA[*/\na > b {}\n/*]]>*/</style>"
+        );
+        assert_eq!(
+            xhtml("<body><script></script>"),
+            "<script xmlns=\"http://www.w3.org/1999/xhtml\"></script>"
+        );
+    }
+
+    #[test]
+    fn cdata_ends_are_split_in_comments() {
+        assert_eq!(
+            xhtml("<body><script>a[b[0]]>c</script>"),
+            "<script xmlns=\"http://www.w3.org/1999/xhtml\">//<![CDATA[\n\
+             a[b[0]]/*]]>*//*<![CDATA[*/>c\n//]]></script>"
+        );
+    }
+
+    #[test]
+    fn void_elements_are_self_closing() {
+        assert_eq!(
+            xhtml("<div><br><img src=a.png></div>"),
+            "<div xmlns=\"http://www.w3.org/1999/xhtml\"><br /><img src=\"a.png\" /></div>"
+        );
+    }
+
+    #[test]
+    fn processing_instructions_are_closed() {
+        let mut dom = Document::parse("<p></p>");
+        let p = dom.select("p").get(0).unwrap();
+        let pi = dom.create_comment("");
+        dom[pi] = Node::ProcessingInstruction(ProcessingInstruction {
+            target: "php".to_string(),
+            data: "echo 1;".to_string(),
+        });
+        dom.append(p, pi);
+
+        assert_eq!(
+            dom.node_ref(p).xhtml(),
+            "<p xmlns=\"http://www.w3.org/1999/xhtml\"><?php echo 1;?></p>"
+        );
+    }
+}
@@ -1,3 +1,44 @@
 #[derive(Debug)]
 
 pub struct DomError {}
+
+/// An error reading a document snapshot.
+#[derive(Debug)]
+pub enum SnapshotError {
+    /// Reading the snapshot failed.
+    Io(std::io::Error),
+    /// The input isn't a snapshot.
+    BadMagic,
+    /// The snapshot was written by an incompatible version.
+    UnsupportedVersion(u16),
+    /// The snapshot is truncated or malformed.
+    Corrupt(&'static str),
+}
+
+impl std::fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SnapshotError::Io(err) => write!(f, "failed to read snapshot: {}", err),
+            SnapshotError::BadMagic => f.write_str("not a document snapshot"),
+            SnapshotError::UnsupportedVersion(version) => {
+                write!(f, "unsupported snapshot version {}", version)
+            }
+            SnapshotError::Corrupt(reason) => write!(f, "corrupt snapshot: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for SnapshotError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SnapshotError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for SnapshotError {
+    fn from(err: std::io::Error) -> Self {
+        SnapshotError::Io(err)
+    }
+}
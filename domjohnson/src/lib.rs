@@ -1,17 +1,25 @@
+pub mod builder;
 mod document;
 mod element;
 mod error;
+pub mod markdown;
 mod matcher;
-mod node;
+pub mod node;
+pub mod readability;
+mod sanitizer;
 mod selection;
 
 pub use self::{
     document::Document,
-    element::NodeRef,
+    element::{node_mut::NodeMut, Indent, MinifyOptions, NodeRef, SerializeOptions},
+    error::SnapshotError,
     matcher::{MatchScope, Matcher, Matches},
+    sanitizer::{Removal, SanitizeReport, Sanitizer},
     selection::Selection,
 };
 
 pub use generational_indextree::NodeId;
 
+pub use html5ever::{serialize::TraversalScope, LocalName, Namespace, QualName};
+
 pub use selectors::attr::CaseSensitivity;
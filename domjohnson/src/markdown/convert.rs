@@ -0,0 +1,756 @@
+use html5ever::{expanded_name, local_name, namespace_url, ns, LocalName};
+
+use crate::element::kind::{collapse_whitespace, is_block_name};
+use crate::node::{Element, Node};
+use crate::NodeRef;
+
+use super::{LinkStyle, MarkdownOptions, UnknownElements};
+
+pub(crate) fn convert(node: NodeRef<'_>, opts: &MarkdownOptions) -> String {
+    let mut converter = Converter {
+        opts,
+        references: Vec::new(),
+        in_table: false,
+    };
+
+    let mut out = match node.node() {
+        Node::Document | Node::Fragment => converter.blocks(node.children(), false),
+        _ => converter.blocks(std::iter::once(node), false),
+    };
+
+    if !converter.references.is_empty() {
+        out.push_str("\n\n");
+        for (idx, (url, title)) in converter.references.iter().enumerate() {
+            out.push_str(&format!("[{}]: {}{}\n", idx + 1, url, title));
+        }
+        out.pop();
+    }
+
+    if !out.is_empty() {
+        out.push('\n');
+    }
+    out
+}
+
+enum Kind {
+    Skip,
+    Inline,
+    Block,
+}
+
+/// A converted block, which knows whether it's a list so that nested lists
+/// can be kept tight.
+struct Block {
+    text: String,
+    list: bool,
+}
+
+struct Converter<'o> {
+    opts: &'o MarkdownOptions,
+    /// Link definitions, as destination and formatted title.
+    references: Vec<(String, String)>,
+    in_table: bool,
+}
+
+impl<'o> Converter<'o> {
+    fn kind(&self, node: NodeRef<'_>) -> Kind {
+        let element = match node.node() {
+            Node::Text(_) => return Kind::Inline,
+            Node::Element(element) => element,
+            _ => return Kind::Skip,
+        };
+
+        if is_skipped(element) {
+            Kind::Skip
+        } else if is_block_name(&element.name) {
+            Kind::Block
+        } else if is_inline(element) {
+            Kind::Inline
+        } else if self.opts.unknown_elements == UnknownElements::Drop {
+            Kind::Skip
+        } else if node
+            .children()
+            .any(|child| matches!(self.kind(child), Kind::Block))
+        {
+            Kind::Block
+        } else {
+            Kind::Inline
+        }
+    }
+
+    /// Converts a run of nodes, wrapping consecutive inline nodes in
+    /// paragraphs. In list items, nested lists directly follow the text
+    /// before them so that the list stays tight.
+    fn blocks<'a>(&mut self, nodes: impl Iterator<Item = NodeRef<'a>>, list_item: bool) -> String {
+        let mut blocks = Vec::new();
+        let mut inline = Inline::default();
+
+        for node in nodes {
+            match self.kind(node) {
+                Kind::Skip => {}
+                Kind::Inline => self.inline(node, &mut inline),
+                Kind::Block => {
+                    paragraph(std::mem::take(&mut inline), &mut blocks);
+                    if let Some(block) = self.block(node) {
+                        blocks.push(block);
+                    }
+                }
+            }
+        }
+        paragraph(inline, &mut blocks);
+
+        let mut out = String::new();
+        for (idx, block) in blocks.iter().enumerate() {
+            if idx > 0 {
+                out.push_str(if list_item && block.list && !blocks[idx - 1].list {
+                    "\n"
+                } else {
+                    "\n\n"
+                });
+            }
+            out.push_str(&block.text);
+        }
+        out
+    }
+
+    fn block(&mut self, node: NodeRef<'_>) -> Option<Block> {
+        let element = node.as_element()?;
+
+        if !is_block_name(&element.name) {
+            let text = match self.opts.unknown_elements {
+                UnknownElements::Html => node.html(),
+                _ => self.blocks(node.children(), false),
+            };
+            return non_empty(text, false);
+        }
+
+        let text = match element.name.local {
+            local_name!("h1")
+            | local_name!("h2")
+            | local_name!("h3")
+            | local_name!("h4")
+            | local_name!("h5")
+            | local_name!("h6") => {
+                let level = element.name.local[1..].parse::<usize>().unwrap_or(1);
+                let text = self.inline_of(node).replace("\\\n", " ");
+                if text.is_empty() {
+                    return None;
+                }
+                format!("{} {}", "#".repeat(level), text)
+            }
+            local_name!("blockquote") => {
+                let text = self.blocks(node.children(), false);
+                if text.is_empty() {
+                    return None;
+                }
+                prefix_lines(&text, ">")
+            }
+            local_name!("ul") | local_name!("ol") => return self.list(node, element),
+            local_name!("li") => {
+                let marker = format!("{} ", self.opts.bullet);
+                self.list_item(node, &marker)
+            }
+            local_name!("pre") => code_block(node),
+            local_name!("hr") => "---".to_string(),
+            local_name!("table") => return self.table(node),
+            _ => self.blocks(node.children(), false),
+        };
+
+        non_empty(text, false)
+    }
+
+    fn list(&mut self, node: NodeRef<'_>, element: &Element) -> Option<Block> {
+        let ordered = element.name.local == local_name!("ol");
+        let mut number = element.int_attr("start").unwrap_or(1);
+
+        let mut items = Vec::new();
+        for item in node.children() {
+            let Some(element) = item.as_element() else {
+                continue;
+            };
+            if element.name.expanded() != expanded_name!(html "li") {
+                if let Some(block) = self.block(item) {
+                    items.push(block.text);
+                }
+                continue;
+            }
+
+            let marker = if ordered {
+                let value = element.int_attr("value").unwrap_or(number);
+                number = value + 1;
+                format!("{}. ", value)
+            } else {
+                format!("{} ", self.opts.bullet)
+            };
+            items.push(self.list_item(item, &marker));
+        }
+
+        non_empty(items.join("\n"), true)
+    }
+
+    fn list_item(&mut self, node: NodeRef<'_>, marker: &str) -> String {
+        let text = self.blocks(node.children(), true);
+        if text.is_empty() {
+            return marker.trim_end().to_string();
+        }
+
+        let indent = " ".repeat(marker.len());
+        let mut out = String::new();
+        for (idx, line) in text.lines().enumerate() {
+            if idx == 0 {
+                out.push_str(marker);
+            } else {
+                out.push('\n');
+                if !line.is_empty() {
+                    out.push_str(&indent);
+                }
+            }
+            out.push_str(line);
+        }
+        out
+    }
+
+    fn table(&mut self, node: NodeRef<'_>) -> Option<Block> {
+        let mut caption = None;
+        let mut rows = Vec::new();
+
+        for child in node.children() {
+            match child.as_element().map(|e| e.name.local.clone()) {
+                Some(local_name!("caption")) => caption = Some(self.inline_of(child)),
+                Some(local_name!("thead") | local_name!("tbody") | local_name!("tfoot")) => {
+                    for row in child.children() {
+                        if is_element(row, local_name!("tr")) {
+                            rows.push(row);
+                        }
+                    }
+                }
+                Some(local_name!("tr")) => rows.push(child),
+                _ => {}
+            }
+        }
+
+        let mut cells = Vec::new();
+        let mut align = Vec::new();
+        self.in_table = true;
+        for (idx, row) in rows.iter().enumerate() {
+            let mut line = Vec::new();
+            for cell in row.children() {
+                let Some(element) = cell.as_element() else {
+                    continue;
+                };
+                if !matches!(element.name.local, local_name!("td") | local_name!("th")) {
+                    continue;
+                }
+
+                line.push(self.inline_of(cell).replace('|', "\\|"));
+                if idx == 0 {
+                    align.push(element.enum_attr(
+                        "align",
+                        &["left", "center", "right"],
+                        None,
+                        None,
+                    ));
+                }
+                for _ in 1..element.col_span() {
+                    line.push(String::new());
+                    if idx == 0 {
+                        align.push(None);
+                    }
+                }
+            }
+            cells.push(line);
+        }
+        self.in_table = false;
+
+        let columns = cells.iter().map(Vec::len).max().unwrap_or(0);
+        if columns == 0 {
+            return caption.and_then(|caption| non_empty(caption, false));
+        }
+        align.resize(columns, None);
+
+        let mut out = String::new();
+        if let Some(caption) = caption.filter(|c| !c.is_empty()) {
+            out.push_str(&caption);
+            out.push_str("\n\n");
+        }
+
+        for (idx, mut line) in cells.into_iter().enumerate() {
+            line.resize(columns, String::new());
+            out.push_str("| ");
+            out.push_str(&line.join(" | "));
+            out.push_str(" |\n");
+
+            if idx == 0 {
+                let separator: Vec<_> = align
+                    .iter()
+                    .map(|align| match align {
+                        Some("left") => ":---",
+                        Some("center") => ":---:",
+                        Some("right") => "---:",
+                        _ => "---",
+                    })
+                    .collect();
+                out.push_str("| ");
+                out.push_str(&separator.join(" | "));
+                out.push_str(" |\n");
+            }
+        }
+        out.pop();
+
+        non_empty(out, false)
+    }
+
+    /// Converts the children of a node as a single line of inline content.
+    fn inline_of(&mut self, node: NodeRef<'_>) -> String {
+        let mut inline = Inline::default();
+        for child in node.children() {
+            self.inline(child, &mut inline);
+        }
+        inline.finish()
+    }
+
+    fn inline(&mut self, node: NodeRef<'_>, out: &mut Inline) {
+        let element = match node.node() {
+            Node::Text(text) => {
+                out.text(&escape(&collapse_whitespace(text)));
+                return;
+            }
+            Node::Element(element) => element,
+            _ => return,
+        };
+
+        if is_skipped(element) {
+            return;
+        }
+
+        if !is_block_name(&element.name) && !is_inline(element) {
+            match self.opts.unknown_elements {
+                UnknownElements::Content => self.inline_children(node, out),
+                UnknownElements::Html => out.raw(&node.html()),
+                UnknownElements::Drop => {}
+            }
+            return;
+        }
+
+        match element.name.local {
+            local_name!("br") => {
+                if self.in_table {
+                    out.raw("<br>");
+                } else {
+                    out.raw("\\\n");
+                }
+            }
+            local_name!("em")
+            | local_name!("i")
+            | local_name!("cite")
+            | local_name!("dfn")
+            | local_name!("var") => self.wrap(node, "*", out),
+            local_name!("strong") | local_name!("b") => self.wrap(node, "**", out),
+            local_name!("del") | local_name!("s") | local_name!("strike") => {
+                self.wrap(node, "~~", out)
+            }
+            local_name!("code") | local_name!("kbd") | local_name!("samp") | local_name!("tt") => {
+                let code = collapse_whitespace(&text_content(node));
+                if !code.trim().is_empty() {
+                    out.raw(&code_span(&code));
+                }
+            }
+            local_name!("a") => self.link(node, element, out),
+            local_name!("img") => {
+                if let Some(src) = element.url_attr("src").filter(|src| !src.is_empty()) {
+                    let alt = element.attr("alt").map_or(String::new(), |alt| {
+                        escape(&collapse_whitespace(alt)).trim().to_string()
+                    });
+                    out.raw(&format!(
+                        "![{}]({}{})",
+                        alt,
+                        destination(src),
+                        title(element)
+                    ));
+                }
+            }
+            local_name!("input") => {
+                if element.input_type() == "checkbox" {
+                    out.raw(if element.bool_attr("checked") {
+                        "[x] "
+                    } else {
+                        "[ ] "
+                    });
+                }
+            }
+            _ if is_block_name(&element.name) => {
+                out.space();
+                self.inline_children(node, out);
+                out.space();
+            }
+            _ => self.inline_children(node, out),
+        }
+    }
+
+    fn inline_children(&mut self, node: NodeRef<'_>, out: &mut Inline) {
+        for child in node.children() {
+            self.inline(child, out);
+        }
+    }
+
+    /// Wraps inline content in emphasis markers, keeping surrounding
+    /// whitespace outside of them as CommonMark requires.
+    fn wrap(&mut self, node: NodeRef<'_>, marker: &str, out: &mut Inline) {
+        let mut inner = Inline::default();
+        self.inline_children(node, &mut inner);
+
+        let text = inner.out;
+        let trimmed = text.trim_matches(' ');
+        if text.starts_with(' ') {
+            out.text(" ");
+        }
+        if !trimmed.is_empty() {
+            out.raw(&format!("{}{}{}", marker, trimmed, marker));
+            if text.ends_with(' ') {
+                out.text(" ");
+            }
+        }
+    }
+
+    fn link(&mut self, node: NodeRef<'_>, element: &Element, out: &mut Inline) {
+        let text = self.inline_of(node);
+        let Some(href) = element.url_attr("href").filter(|href| !href.is_empty()) else {
+            out.raw(&text);
+            return;
+        };
+
+        let is_autolink = (href.starts_with("http://")
+            || href.starts_with("https://")
+            || href.starts_with("mailto:"))
+            && !href.contains([' ', '<', '>'])
+            && text_content(node).trim() == href.trim_start_matches("mailto:");
+        if is_autolink {
+            out.raw(&format!("<{}>", href));
+            return;
+        }
+
+        let text = if text.is_empty() { escape(href) } else { text };
+        match self.opts.link_style {
+            LinkStyle::Inline => {
+                out.raw(&format!(
+                    "[{}]({}{})",
+                    text,
+                    destination(href),
+                    title(element)
+                ));
+            }
+            LinkStyle::Referenced => {
+                let reference = (destination(href), title(element));
+                let idx = match self.references.iter().position(|r| *r == reference) {
+                    Some(idx) => idx,
+                    None => {
+                        self.references.push(reference);
+                        self.references.len() - 1
+                    }
+                };
+                out.raw(&format!("[{}][{}]", text, idx + 1));
+            }
+        }
+    }
+}
+
+/// Inline content being collected, with whitespace collapsed across nodes.
+#[derive(Default)]
+struct Inline {
+    out: String,
+}
+
+impl Inline {
+    /// Appends collapsed, escaped text, dropping a leading space where the
+    /// output already ends with whitespace.
+    fn text(&mut self, text: &str) {
+        let text = match text.strip_prefix(' ') {
+            Some(rest) if self.out.is_empty() || self.out.ends_with([' ', '\n']) => rest,
+            _ => text,
+        };
+        self.out.push_str(text);
+    }
+
+    fn raw(&mut self, text: &str) {
+        self.out.push_str(text);
+    }
+
+    fn space(&mut self) {
+        self.text(" ");
+    }
+
+    fn finish(self) -> String {
+        let text = self.out.trim_matches(' ');
+        let text = text.strip_suffix("\\\n").unwrap_or(text);
+        text.trim_matches(' ').to_string()
+    }
+}
+
+/// Ends a paragraph, escaping what would otherwise start a block.
+fn paragraph(inline: Inline, blocks: &mut Vec<Block>) {
+    let text = inline.finish();
+    if text.is_empty() {
+        return;
+    }
+
+    let digits = text.bytes().take_while(u8::is_ascii_digit).count();
+    let text = if text.starts_with(['#', '-', '+', '>', '=']) {
+        format!("\\{}", text)
+    } else if digits > 0 && text[digits..].starts_with(['.', ')']) {
+        format!("{}\\{}", &text[..digits], &text[digits..])
+    } else {
+        text
+    };
+
+    blocks.push(Block { text, list: false });
+}
+
+fn non_empty(text: String, list: bool) -> Option<Block> {
+    if text.trim().is_empty() {
+        None
+    } else {
+        Some(Block { text, list })
+    }
+}
+
+fn code_block(node: NodeRef<'_>) -> String {
+    let mut elements = node.children().filter(|child| child.is_element());
+    let code = match (elements.next(), elements.next()) {
+        (Some(code), None) if is_element(code, local_name!("code")) => Some(code),
+        _ => None,
+    };
+
+    let language = code
+        .and_then(|code| code.node().as_element())
+        .into_iter()
+        .flat_map(|element| element.classes())
+        .find_map(|class| {
+            class
+                .strip_prefix("language-")
+                .or_else(|| class.strip_prefix("lang-"))
+                .map(str::to_string)
+        })
+        .unwrap_or_default();
+
+    let text = text_content(node);
+    let text = text.strip_suffix('\n').unwrap_or(&text);
+    let fence = "`".repeat(longest_run(text, '`').max(2) + 1);
+
+    format!("{}{}\n{}\n{}", fence, language, text, fence)
+}
+
+fn code_span(code: &str) -> String {
+    let fence = "`".repeat(longest_run(code, '`') + 1);
+    if code.starts_with('`') || code.ends_with('`') {
+        format!("{} {} {}", fence, code, fence)
+    } else {
+        format!("{}{}{}", fence, code, fence)
+    }
+}
+
+fn longest_run(text: &str, c: char) -> usize {
+    let mut longest = 0;
+    let mut run = 0;
+    for ch in text.chars() {
+        if ch == c {
+            run += 1;
+            longest = longest.max(run);
+        } else {
+            run = 0;
+        }
+    }
+    longest
+}
+
+fn prefix_lines(text: &str, prefix: &str) -> String {
+    text.lines()
+        .map(|line| {
+            if line.is_empty() {
+                prefix.to_string()
+            } else {
+                format!("{} {}", prefix, line)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// A link destination, in angle brackets if it has spaces or parentheses.
+fn destination(url: &str) -> String {
+    if url.contains([' ', '(', ')']) {
+        format!("<{}>", url.replace('<', "%3C").replace('>', "%3E"))
+    } else {
+        url.to_string()
+    }
+}
+
+/// A link title with its leading space, or nothing.
+fn title(element: &Element) -> String {
+    match element.attr("title") {
+        Some(title) if !title.trim().is_empty() => format!(
+            " \"{}\"",
+            collapse_whitespace(title)
+                .trim()
+                .replace('\\', "\\\\")
+                .replace('"', "\\\"")
+        ),
+        _ => String::new(),
+    }
+}
+
+fn text_content(node: NodeRef<'_>) -> String {
+    node.text().map(|text| text.as_str()).collect()
+}
+
+/// Escapes characters that could start inline Markdown syntax, and `&`
+/// where it would start a character reference.
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for (i, c) in text.char_indices() {
+        if matches!(c, '\\' | '*' | '_' | '`' | '[' | ']' | '<' | '~')
+            || (c == '&' && starts_reference(&text[i + 1..]))
+        {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// Returns true if `rest`, the text after a `&`, looks like the rest of a
+/// named or numeric character reference.
+fn starts_reference(rest: &str) -> bool {
+    let rest = rest.strip_prefix('#').unwrap_or(rest);
+    let name = rest
+        .find(|c: char| !c.is_ascii_alphanumeric())
+        .unwrap_or(rest.len());
+    name > 0 && rest[name..].starts_with(';')
+}
+
+fn is_element(node: NodeRef<'_>, name: LocalName) -> bool {
+    node.as_element()
+        .is_some_and(|e| e.name.ns == ns!(html) && e.name.local == name)
+}
+
+/// Elements dropped with their contents.
+fn is_skipped(element: &Element) -> bool {
+    element.name.ns == ns!(html)
+        && matches!(
+            element.name.local,
+            local_name!("head")
+                | local_name!("title")
+                | local_name!("script")
+                | local_name!("style")
+                | local_name!("noscript")
+                | local_name!("template")
+                | local_name!("meta")
+                | local_name!("link")
+                | local_name!("base")
+        )
+}
+
+/// Inline elements with a Markdown equivalent or no markup of their own.
+fn is_inline(element: &Element) -> bool {
+    element.name.ns == ns!(html)
+        && matches!(
+            element.name.local,
+            local_name!("a")
+                | local_name!("img")
+                | local_name!("br")
+                | local_name!("em")
+                | local_name!("i")
+                | local_name!("cite")
+                | local_name!("dfn")
+                | local_name!("var")
+                | local_name!("strong")
+                | local_name!("b")
+                | local_name!("del")
+                | local_name!("s")
+                | local_name!("strike")
+                | local_name!("code")
+                | local_name!("kbd")
+                | local_name!("samp")
+                | local_name!("tt")
+                | local_name!("span")
+                | local_name!("u")
+                | local_name!("small")
+                | local_name!("big")
+                | local_name!("mark")
+                | local_name!("abbr")
+                | local_name!("sub")
+                | local_name!("sup")
+                | local_name!("q")
+                | local_name!("time")
+                | local_name!("label")
+                | local_name!("font")
+                | local_name!("bdi")
+                | local_name!("bdo")
+                | local_name!("ins")
+                | local_name!("data")
+                | local_name!("wbr")
+                | local_name!("input")
+        )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::markdown::{LinkStyle, MarkdownOptions, UnknownElements};
+    use crate::Document;
+
+    fn markdown(html: &str) -> String {
+        Document::parse(html).to_markdown(&MarkdownOptions::default())
+    }
+
+    #[test]
+    fn blocks_and_inline_markup() {
+        assert_eq!(
+            markdown("<h1>Title</h1><p>Some <em>text</em>   and\n <strong>bold</strong> <a href='/x'>link</a>.</p>"),
+            "# Title\n\nSome *text* and **bold** [link](/x).\n"
+        );
+        assert_eq!(
+            markdown(
+                "<pre><code>let x = 1;\n  y</code></pre><blockquote><p>quote</p></blockquote><hr>"
+            ),
+            "```\nlet x = 1;\n  y\n```\n\n> quote\n\n---\n"
+        );
+        assert_eq!(
+            markdown("<ul><li>one</li><li>two<ul><li>nested</li></ul></li></ul>"),
+            "- one\n- two\n  - nested\n"
+        );
+        assert_eq!(
+            markdown("<table><tr><th>a</th><th>b</th></tr><tr><td>1</td><td>2</td></tr></table>"),
+            "| a | b |\n| --- | --- |\n| 1 | 2 |\n"
+        );
+    }
+
+    #[test]
+    fn block_elements_split_paragraphs() {
+        assert_eq!(
+            markdown("<center>centered</center>text after <custom><p>block</p></custom>"),
+            "centered\n\ntext after\n\nblock\n"
+        );
+    }
+
+    #[test]
+    fn text_is_escaped() {
+        assert_eq!(
+            markdown("<p>*not* emphasis_ [x]</p><img src=a.png alt='an   image'>"),
+            "\\*not\\* emphasis\\_ \\[x\\]\n\n![an image](a.png)\n"
+        );
+        assert_eq!(
+            markdown("<p>Tom &amp;amp; Jerry &amp; &amp;#169;</p>"),
+            "Tom \\&amp; Jerry & \\&#169;\n"
+        );
+    }
+
+    #[test]
+    fn options() {
+        let opts = MarkdownOptions {
+            link_style: LinkStyle::Referenced,
+            unknown_elements: UnknownElements::Drop,
+            ..MarkdownOptions::default()
+        };
+        let dom = Document::parse("<p><a href='/x'>link</a> <custom>gone</custom></p>");
+        assert_eq!(dom.to_markdown(&opts), "[link][1]\n\n[1]: /x\n");
+    }
+}
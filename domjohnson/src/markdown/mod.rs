@@ -0,0 +1,67 @@
+//! Conversion between HTML and Markdown.
+//!
+//! Converting HTML to Markdown is always available. Parsing Markdown, with
+//! `Document::from_markdown` and `Document::append_markdown`, needs the
+//! `markdown` feature.
+
+use crate::NodeRef;
+
+mod convert;
+#[cfg(feature = "markdown")]
+mod parse;
+
+#[cfg(feature = "markdown")]
+pub(crate) use self::parse::append_markdown;
+
+/// How links are written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LinkStyle {
+    /// `[text](url "title")`.
+    Inline,
+    /// `[text][1]`, with the `[1]: url "title"` definitions at the end.
+    Referenced,
+}
+
+/// What to do with elements that have no Markdown equivalent, like
+/// `video`, form controls or custom elements.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnknownElements {
+    /// Convert their contents.
+    Content,
+    /// Keep them as raw HTML, which CommonMark passes through.
+    Html,
+    /// Drop them with their contents.
+    Drop,
+}
+
+/// Options for Markdown conversion.
+#[derive(Debug, Clone)]
+pub struct MarkdownOptions {
+    /// How links are written.
+    pub link_style: LinkStyle,
+
+    /// What to do with elements that have no Markdown equivalent.
+    pub unknown_elements: UnknownElements,
+
+    /// The bullet for unordered list items: `-`, `*` or `+`.
+    pub bullet: char,
+}
+
+impl Default for MarkdownOptions {
+    fn default() -> Self {
+        MarkdownOptions {
+            link_style: LinkStyle::Inline,
+            unknown_elements: UnknownElements::Content,
+            bullet: '-',
+        }
+    }
+}
+
+/// Converts a node and its descendants to CommonMark, using the GitHub
+/// Flavored Markdown extensions for tables, strikethrough and task lists.
+///
+/// `head`, `script`, `style` and `template` are dropped. Whitespace is
+/// collapsed as in rendered HTML, except in `pre`.
+pub fn to_markdown(node: NodeRef<'_>, opts: &MarkdownOptions) -> String {
+    convert::convert(node, opts)
+}
@@ -0,0 +1,378 @@
+use generational_indextree::NodeId;
+use pulldown_cmark::{Alignment, CodeBlockKind, Event, Options, Parser, Tag, TagEnd};
+
+use crate::element::kind::is_void_name;
+use crate::Document;
+
+/// Appends the nodes for `markdown` to `parent`.
+pub(crate) fn append_markdown(doc: &mut Document, parent: NodeId, markdown: &str) {
+    let options =
+        Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH | Options::ENABLE_TASKLISTS;
+
+    let mut builder = Builder {
+        doc,
+        stack: vec![Frame::single(parent)],
+        html: String::new(),
+        table: None,
+    };
+
+    for event in Parser::new_ext(markdown, options) {
+        builder.event(event);
+    }
+    builder.flush_html();
+}
+
+/// The node that content goes into for one open Markdown tag or inline
+/// HTML start tag. Images collect their alt text instead.
+struct Frame {
+    node: NodeId,
+    alt: Option<String>,
+    /// The tag name, for inline HTML.
+    html: Option<String>,
+}
+
+impl Frame {
+    fn single(node: NodeId) -> Self {
+        Frame {
+            node,
+            alt: None,
+            html: None,
+        }
+    }
+}
+
+struct Table {
+    alignments: Vec<Alignment>,
+    in_head: bool,
+    column: usize,
+}
+
+struct Builder<'d> {
+    doc: &'d mut Document,
+    stack: Vec<Frame>,
+    /// Consecutive HTML blocks, parsed once the next event arrives.
+    html: String,
+    table: Option<Table>,
+}
+
+impl<'d> Builder<'d> {
+    fn event(&mut self, event: Event<'_>) {
+        match event {
+            Event::Html(html) => {
+                self.html.push_str(&html);
+                return;
+            }
+            _ => self.flush_html(),
+        }
+
+        if let Some(alt) = self.stack.last_mut().and_then(|f| f.alt.as_mut()) {
+            match event {
+                Event::Text(text) | Event::Code(text) => alt.push_str(&text),
+                Event::SoftBreak | Event::HardBreak => alt.push(' '),
+                Event::End(TagEnd::Image) => self.end(),
+                // Images nested in the alt text only contribute theirs.
+                Event::Start(Tag::Image { .. }) => self.stack.push(Frame {
+                    node: self.top(),
+                    alt: Some(String::new()),
+                    html: None,
+                }),
+                _ => {}
+            }
+            return;
+        }
+
+        match event {
+            Event::Start(tag) => self.start(tag),
+            Event::End(_) => self.end(),
+            Event::Text(text) => self.text(&text),
+            Event::InlineHtml(html) => self.inline_html(&html),
+            Event::Code(code) => {
+                let element = self.element("code");
+                let text = self.doc.create_text(&*code);
+                self.doc.append(element, text);
+            }
+            Event::SoftBreak => self.text("\n"),
+            Event::HardBreak => {
+                self.element("br");
+            }
+            Event::Rule => {
+                self.element("hr");
+            }
+            Event::TaskListMarker(checked) => {
+                let input = self.element("input");
+                let element = self.doc[input].as_element_mut().expect("element");
+                element.set_attr("type", "checkbox");
+                element.set_bool_attr("disabled", true);
+                element.set_bool_attr("checked", checked);
+                self.text(" ");
+            }
+            _ => {}
+        }
+    }
+
+    fn start(&mut self, tag: Tag<'_>) {
+        let frame = match tag {
+            Tag::Paragraph => self.element("p"),
+            Tag::Heading { level, .. } => self.element(&level.to_string()),
+            Tag::BlockQuote(_) => self.element("blockquote"),
+            Tag::CodeBlock(kind) => {
+                let pre = self.element("pre");
+                let code = self.doc.create_element("code");
+                self.doc.append(pre, code);
+                if let CodeBlockKind::Fenced(info) = kind {
+                    if let Some(language) = info.split_ascii_whitespace().next() {
+                        let element = self.doc[code].as_element_mut().expect("element");
+                        element.append_class(&format!("language-{}", language));
+                    }
+                }
+                code
+            }
+            Tag::List(Some(start)) => {
+                let list = self.element("ol");
+                if start != 1 {
+                    let element = self.doc[list].as_element_mut().expect("element");
+                    element.set_attr("start", &start.to_string());
+                }
+                list
+            }
+            Tag::List(None) => self.element("ul"),
+            Tag::Item => self.element("li"),
+            Tag::Table(alignments) => {
+                self.table = Some(Table {
+                    alignments,
+                    in_head: false,
+                    column: 0,
+                });
+                self.element("table")
+            }
+            Tag::TableHead => {
+                if let Some(table) = self.table.as_mut() {
+                    table.in_head = true;
+                    table.column = 0;
+                }
+                let head = self.element("thead");
+                let row = self.doc.create_element("tr");
+                self.doc.append(head, row);
+                row
+            }
+            Tag::TableRow => {
+                if let Some(table) = self.table.as_mut() {
+                    table.in_head = false;
+                    table.column = 0;
+                }
+                let table = self.top();
+                let body = match self.doc.node_ref(table).reverse_children().next() {
+                    Some(last) if last.as_element().is_some_and(|e| e.name() == "tbody") => last.id,
+                    _ => {
+                        let body = self.doc.create_element("tbody");
+                        self.doc.append(table, body);
+                        body
+                    }
+                };
+                let row = self.doc.create_element("tr");
+                self.doc.append(body, row);
+                row
+            }
+            Tag::TableCell => {
+                let (name, align) = match self.table.as_mut() {
+                    Some(table) => {
+                        let align = table.alignments.get(table.column).copied();
+                        table.column += 1;
+                        (if table.in_head { "th" } else { "td" }, align)
+                    }
+                    None => ("td", None),
+                };
+                let cell = self.element(name);
+                let align = match align {
+                    Some(Alignment::Left) => Some("left"),
+                    Some(Alignment::Center) => Some("center"),
+                    Some(Alignment::Right) => Some("right"),
+                    _ => None,
+                };
+                if let Some(align) = align {
+                    let element = self.doc[cell].as_element_mut().expect("element");
+                    element.set_attr("align", align);
+                }
+                cell
+            }
+            Tag::Emphasis => self.element("em"),
+            Tag::Strong => self.element("strong"),
+            Tag::Strikethrough => self.element("del"),
+            Tag::Link {
+                dest_url, title, ..
+            } => {
+                let link = self.element("a");
+                let element = self.doc[link].as_element_mut().expect("element");
+                element.set_attr("href", &dest_url);
+                if !title.is_empty() {
+                    element.set_attr("title", &title);
+                }
+                link
+            }
+            Tag::Image {
+                dest_url, title, ..
+            } => {
+                let image = self.element("img");
+                let element = self.doc[image].as_element_mut().expect("element");
+                element.set_attr("src", &dest_url);
+                if !title.is_empty() {
+                    element.set_attr("title", &title);
+                }
+                self.stack.push(Frame {
+                    node: image,
+                    alt: Some(String::new()),
+                    html: None,
+                });
+                return;
+            }
+            // Raw HTML blocks arrive as `Html` events.
+            _ => self.top(),
+        };
+
+        self.stack.push(Frame::single(frame));
+    }
+
+    fn end(&mut self) {
+        // Inline HTML left open ends with the Markdown tag around it.
+        while self.stack.last().is_some_and(|f| f.html.is_some()) {
+            self.stack.pop();
+        }
+
+        let Some(frame) = self.stack.pop() else {
+            return;
+        };
+
+        if let Some(alt) = frame.alt {
+            // Nested images don't get an element of their own.
+            match self.stack.last_mut().and_then(|f| f.alt.as_mut()) {
+                Some(outer) => outer.push_str(&alt),
+                None => {
+                    let element = self.doc[frame.node].as_element_mut().expect("element");
+                    element.set_attr("alt", &alt);
+                }
+            }
+        }
+    }
+
+    fn top(&self) -> NodeId {
+        self.stack.last().expect("no parent").node
+    }
+
+    /// Appends a new element to the current node.
+    fn element(&mut self, name: &str) -> NodeId {
+        let element = self.doc.create_element(name);
+        self.doc.append(self.top(), element);
+        element
+    }
+
+    /// Appends text to the current node, merging it with a preceding text
+    /// node as the parser splits text around escapes and entities.
+    fn text(&mut self, text: &str) {
+        let parent = self.top();
+        let last = self.doc.node_ref(parent).reverse_children().next();
+        if let Some(last) = last.filter(|last| last.is_text()) {
+            let id = last.id;
+            if let Some(node) = self.doc[id].as_text_mut() {
+                node.concat(text);
+                return;
+            }
+        }
+
+        let node = self.doc.create_text(text);
+        self.doc.append(parent, node);
+    }
+
+    /// Inline HTML comes one tag at a time. A start tag is kept open so
+    /// that the Markdown up to its end tag goes inside it.
+    fn inline_html(&mut self, html: &str) {
+        if let Some(name) = html.strip_prefix("</") {
+            let name = name.trim_end_matches('>').trim().to_ascii_lowercase();
+            if let Some(idx) = self
+                .stack
+                .iter()
+                .rposition(|f| f.html.as_deref() == Some(&name))
+            {
+                self.stack.truncate(idx);
+            }
+            return;
+        }
+
+        let parent = self.top();
+        let before = self.doc.children(parent).count();
+        self.doc.append_html(parent, html);
+        let added: Vec<_> = self.doc.children(parent).skip(before).collect();
+
+        let [node] = added[..] else {
+            return;
+        };
+        if html.trim_end().ends_with("/>") || self.doc.children(node).next().is_some() {
+            return;
+        }
+        let name = match self.doc[node].as_element() {
+            Some(element) if !is_void_name(&element.name) => element.name.local.to_string(),
+            _ => return,
+        };
+
+        self.stack.push(Frame {
+            node,
+            alt: None,
+            html: Some(name),
+        });
+    }
+
+    fn flush_html(&mut self) {
+        if !self.html.is_empty() {
+            let html = std::mem::take(&mut self.html);
+            self.doc.append_html(self.top(), &html);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::markdown::MarkdownOptions;
+    use crate::Document;
+
+    fn html(markdown: &str) -> String {
+        let dom = Document::from_markdown(markdown);
+        let body = dom.select("body").get(0).unwrap();
+        dom.inner_html(body)
+    }
+
+    #[test]
+    fn markdown_round_trips() {
+        for markdown in [
+            "# Title\n\nSome *text* and **bold** [link](/x \"t\").\n",
+            "- one\n- two\n  - nested\n",
+            "1. a\n2. b\n",
+            "```rust\nlet x = 1;\n```\n\n> quote\n\n---\n",
+            "| a | b |\n| --- | --- |\n| 1 | 2 |\n",
+            "- [x] done\n- [ ] todo\n",
+            "~~gone~~ and ![alt text](a.png)\n",
+            "Tom \\&amp; Jerry & \\&#169; 2024\n",
+        ] {
+            let dom = Document::from_markdown(markdown);
+            assert_eq!(dom.to_markdown(&MarkdownOptions::default()), markdown);
+        }
+    }
+
+    #[test]
+    fn markdown_becomes_html() {
+        assert_eq!(
+            html("# Title\n\n- a\n- b\n"),
+            "<h1>Title</h1><ul><li>a</li><li>b</li></ul>"
+        );
+        assert_eq!(
+            html("```rust\nx\n```\n"),
+            "<pre><code class=\"language-rust\">x\n</code></pre>"
+        );
+    }
+
+    #[test]
+    fn raw_html_is_parsed_in_place() {
+        assert_eq!(
+            html("~~gone~~ and <kbd>Ctrl</kbd>\n\n<div>\nblock\n</div>\n"),
+            "<p><del>gone</del> and <kbd>Ctrl</kbd></p><div>\nblock\n</div>\n"
+        );
+    }
+}
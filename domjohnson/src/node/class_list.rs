@@ -0,0 +1,168 @@
+use html5ever::LocalName;
+use std::ops::Deref;
+
+use super::{Classes, Element};
+
+/// A DOM-like view over an element's classes. Classes keep their source
+/// order, and every change is written back to the `class` attribute.
+pub struct ClassList<'a> {
+    element: &'a mut Element,
+}
+
+impl<'a> ClassList<'a> {
+    pub(crate) fn new(element: &'a mut Element) -> Self {
+        ClassList { element }
+    }
+
+    /// Returns true if the class is present.
+    pub fn contains(&self, class: &str) -> bool {
+        self.element.classes.iter().any(|c| c.deref() == class)
+    }
+
+    /// Returns an iterator over the classes in order.
+    pub fn iter(&self) -> Classes<'_> {
+        self.element.classes()
+    }
+
+    /// Returns the number of classes.
+    pub fn len(&self) -> usize {
+        self.element.classes.len()
+    }
+
+    /// Returns true if there are no classes.
+    pub fn is_empty(&self) -> bool {
+        self.element.classes.is_empty()
+    }
+
+    /// Adds the classes that aren't already present. The argument is split
+    /// on ASCII whitespace, so `add("a b")` adds two classes and `add("")`
+    /// adds none.
+    pub fn add(&mut self, classes: &str) {
+        let mut changed = false;
+        for class in classes.split_ascii_whitespace() {
+            if !self.contains(class) {
+                self.element.classes.push(LocalName::from(class));
+                changed = true;
+            }
+        }
+        if changed {
+            self.element.write_classes();
+        }
+    }
+
+    /// Removes the classes that are present, splitting the argument like
+    /// [`ClassList::add`].
+    pub fn remove(&mut self, classes: &str) {
+        let classes = classes.split_ascii_whitespace().collect::<Vec<_>>();
+        if classes.iter().any(|class| self.contains(class)) {
+            self.element
+                .classes
+                .retain(|c| !classes.contains(&c.deref()));
+            self.element.write_classes();
+        }
+    }
+
+    /// Removes the class if present, otherwise adds it. Returns true if the
+    /// class is present afterwards. Does nothing and returns false if
+    /// `class` is empty or has whitespace.
+    pub fn toggle(&mut self, class: &str) -> bool {
+        if !is_token(class) {
+            false
+        } else if self.contains(class) {
+            self.remove(class);
+            false
+        } else {
+            self.add(class);
+            true
+        }
+    }
+
+    /// Replaces `old` with `new`, keeping the position of whichever comes
+    /// first. Returns false if `old` wasn't present or `new` is empty or has
+    /// whitespace.
+    pub fn replace(&mut self, old: &str, new: &str) -> bool {
+        if !self.contains(old) || !is_token(new) {
+            return false;
+        }
+
+        let classes = &mut self.element.classes;
+        let first = classes
+            .iter()
+            .position(|c| c.deref() == old || c.deref() == new)
+            .unwrap_or_default();
+
+        let mut idx = 0;
+        classes.retain(|c| {
+            idx += 1;
+            idx - 1 == first || (c.deref() != old && c.deref() != new)
+        });
+        classes[first] = LocalName::from(new);
+
+        self.element.write_classes();
+        true
+    }
+}
+
+/// Returns true for a single class: non-empty and without whitespace.
+fn is_token(class: &str) -> bool {
+    !class.is_empty() && !class.contains(|c: char| c.is_ascii_whitespace())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Document;
+
+    #[test]
+    fn add_splits_on_whitespace() {
+        let mut dom = Document::parse(r#"<p class="x">a</p>"#);
+        let p = dom.select("p").get(0).unwrap();
+
+        let element = dom[p].as_element_mut().unwrap();
+        element.append_class("a b");
+        element.append_class("");
+        element.class_list().add(" \tb  c\n");
+
+        let element = dom[p].as_element().unwrap();
+        assert_eq!(element.classes().collect::<Vec<_>>(), ["x", "a", "b", "c"]);
+        assert_eq!(element.attr("class").unwrap(), "x a b c");
+        assert_eq!(dom.select(".a.b.c").len(), 1);
+    }
+
+    #[test]
+    fn invalid_tokens_are_ignored() {
+        let mut dom = Document::parse(r#"<p class="x">a</p>"#);
+        let p = dom.select("p").get(0).unwrap();
+
+        let mut classes = dom[p].as_element_mut().unwrap().class_list();
+        assert!(!classes.toggle("a b"));
+        assert!(!classes.toggle(""));
+        assert!(!classes.replace("x", "y z"));
+        assert!(!classes.replace("x", ""));
+        classes.remove("x y");
+
+        assert_eq!(dom[p].as_element().unwrap().attr("class").unwrap(), "");
+        assert_eq!(dom.select(".x").len(), 0);
+    }
+
+    #[test]
+    fn edits_keep_source_order() {
+        let mut dom = Document::parse(r#"<p class="a b c">t</p>"#);
+        let p = dom.select("p").get(0).unwrap();
+
+        let mut classes = dom[p].as_element_mut().unwrap().class_list();
+        assert!(classes.contains("b"));
+        assert_eq!(classes.len(), 3);
+
+        assert!(!classes.toggle("b"));
+        assert!(classes.toggle("d"));
+        assert!(classes.replace("a", "e"));
+        assert!(!classes.replace("x", "y"));
+        assert_eq!(classes.iter().collect::<Vec<_>>(), ["e", "c", "d"]);
+
+        // Replacing with a present class keeps the first position.
+        assert!(classes.replace("d", "e"));
+        assert_eq!(classes.iter().collect::<Vec<_>>(), ["e", "c"]);
+
+        assert_eq!(dom[p].as_element().unwrap().attr("class").unwrap(), "e c");
+    }
+}
@@ -0,0 +1,173 @@
+use html5ever::namespace_url;
+use html5ever::ns;
+
+use super::{AttributesIter, Element};
+
+/// A read-only view over an element's `data-*` attributes, keyed by their
+/// camelCase names (`data-foo-bar` is `fooBar`).
+#[derive(Clone, Copy)]
+pub struct Dataset<'a> {
+    element: &'a Element,
+}
+
+impl<'a> Dataset<'a> {
+    pub(crate) fn new(element: &'a Element) -> Self {
+        Dataset { element }
+    }
+
+    /// Returns the value for a camelCase name.
+    pub fn get(&self, name: &str) -> Option<&'a str> {
+        get(self.element, name)
+    }
+
+    /// Returns an iterator over the camelCase names and values.
+    pub fn iter(&self) -> DatasetIter<'a> {
+        DatasetIter {
+            inner: self.element.attrs.iter(),
+        }
+    }
+}
+
+/// An editable view over an element's `data-*` attributes.
+pub struct DatasetMut<'a> {
+    element: &'a mut Element,
+}
+
+impl<'a> DatasetMut<'a> {
+    pub(crate) fn new(element: &'a mut Element) -> Self {
+        DatasetMut { element }
+    }
+
+    /// Returns the value for a camelCase name.
+    pub fn get(&self, name: &str) -> Option<&str> {
+        get(self.element, name)
+    }
+
+    /// Sets the value for a camelCase name. Returns false, leaving the
+    /// element untouched, if the name contains a `-` followed by a lowercase
+    /// letter, which has no attribute equivalent.
+    pub fn set(&mut self, name: &str, value: &str) -> bool {
+        match prop_to_attr(name) {
+            Some(attr) => {
+                self.element.set_attr(&attr, value);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Removes the value for a camelCase name.
+    pub fn remove(&mut self, name: &str) {
+        if let Some(attr) = prop_to_attr(name) {
+            self.element.remove_attr(&attr);
+        }
+    }
+
+    /// Returns an iterator over the camelCase names and values.
+    pub fn iter(&self) -> DatasetIter<'_> {
+        DatasetIter {
+            inner: self.element.attrs.iter(),
+        }
+    }
+}
+
+/// Iterator over an element's `data-*` attributes.
+#[allow(missing_debug_implementations)]
+pub struct DatasetIter<'a> {
+    inner: AttributesIter<'a>,
+}
+
+impl<'a> Iterator for DatasetIter<'a> {
+    type Item = (String, &'a str);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.find_map(|(key, value)| {
+            if key.ns != ns!() {
+                return None;
+            }
+            attr_to_prop(&key.local).map(|name| (name, value.as_str()))
+        })
+    }
+}
+
+fn get<'a>(element: &'a Element, name: &str) -> Option<&'a str> {
+    prop_to_attr(name).and_then(|attr| element.attr(&attr).map(String::as_str))
+}
+
+/// Converts `data-foo-bar` to `fooBar`, returning `None` for attributes
+/// that aren't `data-*` attributes.
+fn attr_to_prop(attr: &str) -> Option<String> {
+    let rest = attr.strip_prefix("data-")?;
+    let mut name = String::with_capacity(rest.len());
+    let mut chars = rest.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match chars.peek() {
+            Some(next) if c == '-' && next.is_ascii_lowercase() => {
+                name.push(next.to_ascii_uppercase());
+                chars.next();
+            }
+            _ => name.push(c),
+        }
+    }
+
+    Some(name)
+}
+
+/// Converts `fooBar` to `data-foo-bar`, returning `None` if the name
+/// contains a `-` followed by a lowercase letter.
+fn prop_to_attr(name: &str) -> Option<String> {
+    let mut attr = String::from("data-");
+    let mut chars = name.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c == '-' && chars.peek().is_some_and(char::is_ascii_lowercase) {
+            return None;
+        }
+
+        if c.is_ascii_uppercase() {
+            attr.push('-');
+            attr.push(c.to_ascii_lowercase());
+        } else {
+            attr.push(c);
+        }
+    }
+
+    Some(attr)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{attr_to_prop, prop_to_attr};
+    use crate::Document;
+
+    #[test]
+    fn names_convert_both_ways() {
+        assert_eq!(attr_to_prop("data-foo-bar").as_deref(), Some("fooBar"));
+        assert_eq!(attr_to_prop("data-foo-1").as_deref(), Some("foo-1"));
+        assert_eq!(attr_to_prop("title"), None);
+        assert_eq!(prop_to_attr("fooBar").as_deref(), Some("data-foo-bar"));
+        assert_eq!(prop_to_attr("foo-bar"), None);
+    }
+
+    #[test]
+    fn dataset_reads_and_writes_attributes() {
+        let mut dom = Document::parse(r#"<p data-user-id="7" title="t">t</p>"#);
+        let p = dom.select("p").get(0).unwrap();
+
+        let element = dom[p].as_element_mut().unwrap();
+        assert_eq!(element.dataset().get("userId"), Some("7"));
+        assert_eq!(
+            element.dataset().iter().collect::<Vec<_>>(),
+            [("userId".to_string(), "7")]
+        );
+
+        let mut dataset = element.dataset_mut();
+        assert!(dataset.set("lastSeen", "now"));
+        assert!(!dataset.set("bad-name", "x"));
+        dataset.remove("userId");
+
+        assert_eq!(element.attr("data-last-seen").unwrap(), "now");
+        assert!(element.attr("data-user-id").is_none());
+    }
+}
@@ -1,15 +1,34 @@
 //! HTML nodes.
-use html5ever::{namespace_url, ns, Attribute, LocalName, QualName};
+use html5ever::{local_name, namespace_url, ns, Attribute, LocalName, QualName};
 use selectors::attr::CaseSensitivity;
 use smol_str::SmolStr;
 use std::{
-    collections::{hash_map, hash_set, HashMap, HashSet},
+    collections::{hash_map, HashMap},
     fmt,
     ops::Deref,
+    slice,
+};
+
+mod class_list;
+mod dataset;
+mod reflect;
+#[cfg(feature = "serde")]
+mod serde_impl;
+mod style;
+
+pub use self::{
+    class_list::ClassList,
+    dataset::{Dataset, DatasetMut},
+    style::{Declaration, Style, StyleMut},
 };
 
 /// An HTML node.
 #[derive(Clone, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(tag = "type", rename_all = "snake_case")
+)]
 pub enum Node {
     /// The document root.
     Document,
@@ -136,6 +155,7 @@ impl fmt::Debug for Node {
 
 /// A doctype.
 #[derive(Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Doctype {
     /// The doctype name.
     pub name: SmolStr,
@@ -178,6 +198,7 @@ impl fmt::Debug for Doctype {
 
 /// An HTML comment.
 #[derive(Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Comment {
     /// The comment text.
     pub comment: SmolStr,
@@ -199,6 +220,7 @@ impl fmt::Debug for Comment {
 
 /// HTML text.
 #[derive(Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Text {
     /// The text.
     pub text: SmolStr,
@@ -239,49 +261,41 @@ pub type Attributes = indexmap::IndexMap<QualName, String>;
 pub type Attributes = HashMap<QualName, String>;
 
 /// An HTML element.
+///
+/// The attribute map is the single source of truth; the ID and classes are
+/// cached from the `id` and `class` attributes and kept in sync by every
+/// mutating method.
 #[derive(Clone, PartialEq, Eq)]
 pub struct Element {
     /// The element name.
     pub name: QualName,
 
     /// The element ID.
-    pub id: Option<LocalName>,
+    pub(crate) id: Option<LocalName>,
 
-    /// The element classes.
-    pub classes: HashSet<LocalName>,
+    /// The element classes, in source order and without duplicates.
+    pub(crate) classes: Vec<LocalName>,
 
     /// The element attributes.
-    pub attrs: Attributes,
+    pub(crate) attrs: Attributes,
 }
 
 impl Element {
     #[doc(hidden)]
     pub fn new(name: QualName, attrs: Vec<Attribute>) -> Self {
-        let id = attrs
-            .iter()
-            .find(|a| a.name.local.deref() == "id")
-            .map(|a| LocalName::from(a.value.deref()));
-
-        let classes: HashSet<LocalName> = attrs
-            .iter()
-            .find(|a| a.name.local.deref() == "class")
-            .map_or(HashSet::new(), |a| {
-                a.value
-                    .deref()
-                    .split_whitespace()
-                    .map(LocalName::from)
-                    .collect()
-            });
-
-        Element {
+        let mut element = Element {
             attrs: attrs
                 .into_iter()
                 .map(|a| (a.name, a.value.into()))
                 .collect(),
             name,
-            id,
-            classes,
-        }
+            id: None,
+            classes: Vec::new(),
+        };
+
+        element.sync(&attr_name("id"));
+        element.sync(&attr_name("class"));
+        element
     }
 
     /// Returns the element name.
@@ -300,14 +314,19 @@ impl Element {
             .any(|c| case_sensitive.eq(c.as_bytes(), class.as_bytes()))
     }
 
+    /// Adds a class, updating the `class` attribute.
     pub fn append_class(&mut self, class: &str) {
-        self.classes.insert(LocalName::from(class));
+        self.class_list().add(class)
     }
 
+    /// Removes a class, updating the `class` attribute.
     pub fn remove_class(&mut self, class: &str) {
-        if self.has_class(class, CaseSensitivity::CaseSensitive) {
-            self.classes.remove(&LocalName::from(class));
-        }
+        self.class_list().remove(class)
+    }
+
+    /// Returns an editable, ordered view over the element's classes.
+    pub fn class_list(&mut self) -> ClassList<'_> {
+        ClassList::new(self)
     }
 
     /// Returns an iterator over the element's classes.
@@ -322,23 +341,93 @@ impl Element {
             .any(|(c, _)| case_sensitive.eq(c.as_bytes(), attr.as_bytes()))
     }
 
-    /// Returns the value of an attribute.
+    /// Returns the value of an attribute without a namespace.
     pub fn attr(&self, attr: &str) -> Option<&String> {
-        let qualname = QualName::new(None, ns!(), LocalName::from(attr));
-        self.attrs.get(&qualname)
+        self.attr_ns(&attr_name(attr))
     }
 
+    /// Sets an attribute without a namespace.
     pub fn set_attr(&mut self, attr: &str, value: &str) {
-        self.attrs.insert(
-            QualName::new(None, ns!(html), LocalName::from(attr)),
-            value.into(),
-        );
+        self.set_attr_ns(attr_name(attr), value)
     }
 
+    /// Removes an attribute without a namespace.
     pub fn remove_attr(&mut self, attr: &str) {
-        if self.has_attr(attr, CaseSensitivity::CaseSensitive) {
-            self.attrs
-                .remove(&QualName::new(None, ns!(), LocalName::from(attr)));
+        self.remove_attr_ns(&attr_name(attr))
+    }
+
+    /// Returns a view over the element's `data-*` attributes.
+    pub fn dataset(&self) -> Dataset<'_> {
+        Dataset::new(self)
+    }
+
+    /// Returns an editable view over the element's `data-*` attributes.
+    pub fn dataset_mut(&mut self) -> DatasetMut<'_> {
+        DatasetMut::new(self)
+    }
+
+    /// Returns the parsed `style` attribute.
+    pub fn style(&self) -> Style {
+        self.attr("style")
+            .map_or_else(Style::default, |css| Style::parse(css))
+    }
+
+    /// Writes `style` to the `style` attribute, removing the attribute if
+    /// there are no declarations.
+    pub fn set_style(&mut self, style: &Style) {
+        if style.is_empty() {
+            self.remove_attr("style");
+        } else {
+            self.set_attr("style", &style.to_string());
+        }
+    }
+
+    /// Returns an editable view over the `style` attribute.
+    pub fn style_mut(&mut self) -> StyleMut<'_> {
+        StyleMut::new(self)
+    }
+
+    /// Returns true if element has the namespaced attribute.
+    pub fn has_attr_ns(&self, name: &QualName) -> bool {
+        self.attr_ns(name).is_some()
+    }
+
+    /// Returns the value of a namespaced attribute, like `xlink:href`.
+    /// Attributes are matched on namespace and local name; the prefix is
+    /// ignored.
+    pub fn attr_ns(&self, name: &QualName) -> Option<&String> {
+        self.attrs
+            .iter()
+            .find(|(key, _)| same_attr(key, name))
+            .map(|(_, value)| value)
+    }
+
+    /// Sets a namespaced attribute. An existing attribute keeps its prefix
+    /// and position.
+    pub fn set_attr_ns(&mut self, name: QualName, value: &str) {
+        match self.attrs.iter_mut().find(|(key, _)| same_attr(key, &name)) {
+            Some((_, existing)) => *existing = value.into(),
+            None => {
+                self.attrs.insert(name.clone(), value.into());
+            }
+        }
+        self.sync(&name);
+    }
+
+    /// Removes a namespaced attribute.
+    pub fn remove_attr_ns(&mut self, name: &QualName) {
+        let key = self.attrs.keys().find(|key| same_attr(key, name)).cloned();
+        if let Some(key) = key {
+            remove_entry(&mut self.attrs, &key);
+            self.sync(name);
+        }
+    }
+
+    /// Sets an attribute unless it's already present.
+    pub(crate) fn add_attr_if_missing(&mut self, name: QualName, value: String) {
+        if !self.has_attr_ns(&name) {
+            self.attrs.insert(name.clone(), value);
+            self.sync(&name);
         }
     }
 
@@ -348,13 +437,74 @@ impl Element {
             inner: self.attrs.iter(),
         }
     }
+
+    /// Returns an iterator over the element's attributes with their
+    /// qualified names.
+    pub fn attrs_ns(&self) -> AttributesIter<'_> {
+        self.attrs.iter()
+    }
+
+    /// Refreshes the cached ID or classes if `name` is one of their attributes.
+    fn sync(&mut self, name: &QualName) {
+        if name.ns != ns!() {
+            return;
+        }
+
+        match name.local {
+            local_name!("id") => {
+                self.id = self.attrs.get(name).map(|v| LocalName::from(v.deref()));
+            }
+            local_name!("class") => {
+                self.classes.clear();
+                if let Some(value) = self.attrs.get(name) {
+                    for class in value.split_ascii_whitespace() {
+                        if !self.classes.iter().any(|c| c.deref() == class) {
+                            self.classes.push(LocalName::from(class));
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Writes the cached classes back to the `class` attribute.
+    fn write_classes(&mut self) {
+        let value = self
+            .classes
+            .iter()
+            .map(Deref::deref)
+            .collect::<Vec<_>>()
+            .join(" ");
+        self.attrs.insert(attr_name("class"), value);
+    }
+}
+
+/// Returns true if both names refer to the same attribute, regardless of prefix.
+fn same_attr(a: &QualName, b: &QualName) -> bool {
+    a.ns == b.ns && a.local == b.local
+}
+
+/// The qualified name of an attribute without a namespace.
+fn attr_name(attr: &str) -> QualName {
+    QualName::new(None, ns!(), LocalName::from(attr))
+}
+
+#[cfg(feature = "deterministic")]
+fn remove_entry(attrs: &mut Attributes, name: &QualName) -> Option<String> {
+    attrs.shift_remove(name)
+}
+
+#[cfg(not(feature = "deterministic"))]
+fn remove_entry(attrs: &mut Attributes, name: &QualName) -> Option<String> {
+    attrs.remove(name)
 }
 
 /// Iterator over classes.
 #[allow(missing_debug_implementations)]
 #[derive(Clone)]
 pub struct Classes<'a> {
-    inner: hash_set::Iter<'a, LocalName>,
+    inner: slice::Iter<'a, LocalName>,
 }
 
 impl<'a> Iterator for Classes<'a> {
@@ -408,6 +558,7 @@ impl fmt::Debug for Element {
 
 /// HTML Processing Instruction.
 #[derive(Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ProcessingInstruction {
     /// The PI target.
     pub target: String,
@@ -428,3 +579,84 @@ impl fmt::Debug for ProcessingInstruction {
         write!(f, "{:?} {:?}", self.target, self.data)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use html5ever::{local_name, namespace_url, ns, LocalName, QualName};
+
+    use crate::Document;
+
+    #[test]
+    fn id_and_classes_follow_attributes() {
+        let mut dom = Document::parse(r#"<p id="a" class="x y x">t</p>"#);
+        let p = dom.select("p").get(0).unwrap();
+
+        let element = dom[p].as_element_mut().unwrap();
+        assert_eq!(element.id(), Some("a"));
+        assert_eq!(element.classes().collect::<Vec<_>>(), ["x", "y"]);
+
+        element.set_attr("id", "b");
+        element.set_attr("class", "z\tw");
+        assert_eq!(element.id(), Some("b"));
+        assert_eq!(element.classes().collect::<Vec<_>>(), ["z", "w"]);
+        assert_eq!(dom.select("#b.z.w").len(), 1);
+
+        let element = dom[p].as_element_mut().unwrap();
+        element.remove_attr("id");
+        element.remove_attr("class");
+        assert_eq!(element.id(), None);
+        assert_eq!(element.classes().count(), 0);
+        assert_eq!(dom.select("#b, .z").len(), 0);
+    }
+
+    #[test]
+    fn class_changes_are_written_to_the_attribute() {
+        let mut dom = Document::parse(r#"<p class="x">t</p>"#);
+        let p = dom.select("p").get(0).unwrap();
+
+        let element = dom[p].as_element_mut().unwrap();
+        element.append_class("y");
+        element.remove_class("x");
+        assert_eq!(element.attr("class").unwrap(), "y");
+        assert_eq!(
+            dom.to_string(),
+            r#"<html><head></head><body><p class="y">t</p></body></html>"#
+        );
+    }
+
+    #[test]
+    fn namespaced_attributes() {
+        let mut dom = Document::parse(r##"<svg><use xlink:href="#a"/></svg>"##);
+        let node = dom.select("use").get(0).unwrap();
+        let xlink = |local: &str| QualName::new(None, ns!(xlink), LocalName::from(local));
+
+        let element = dom[node].as_element_mut().unwrap();
+        assert_eq!(element.attr_ns(&xlink("href")).unwrap(), "#a");
+        assert!(element.attr("href").is_none());
+
+        // Existing attributes keep their prefix.
+        element.set_attr_ns(xlink("href"), "#b");
+        let (name, value) = element.attrs_ns().next().unwrap();
+        assert_eq!(name.prefix.as_deref(), Some("xlink"));
+        assert_eq!(value, "#b");
+
+        element.remove_attr_ns(&xlink("href"));
+        assert!(!element.has_attr_ns(&xlink("href")));
+    }
+
+    #[test]
+    fn set_attr_is_read_back_without_namespace() {
+        let mut dom = Document::parse("<p>t</p>");
+        let p = dom.select("p").get(0).unwrap();
+
+        let element = dom[p].as_element_mut().unwrap();
+        element.set_attr("title", "x");
+        assert_eq!(element.attr("title").unwrap(), "x");
+        assert_eq!(
+            element
+                .attr_ns(&QualName::new(None, ns!(), local_name!("title")))
+                .unwrap(),
+            "x"
+        );
+    }
+}
@@ -0,0 +1,252 @@
+use html5ever::{local_name, namespace_url, ns};
+
+use super::Element;
+
+/// Typed attribute accessors following the HTML reflection rules.
+impl Element {
+    /// Returns true if the boolean attribute is present, whatever its value.
+    pub fn bool_attr(&self, attr: &str) -> bool {
+        self.attr(attr).is_some()
+    }
+
+    /// Sets a boolean attribute to the empty string, or removes it.
+    pub fn set_bool_attr(&mut self, attr: &str, value: bool) {
+        if value {
+            self.set_attr(attr, "");
+        } else {
+            self.remove_attr(attr);
+        }
+    }
+
+    /// Returns the attribute parsed with the rules for parsing integers, or
+    /// `None` if it's missing or invalid.
+    pub fn int_attr(&self, attr: &str) -> Option<i32> {
+        self.attr(attr).and_then(|value| parse_integer(value))
+    }
+
+    /// Returns the attribute parsed with the rules for parsing non-negative
+    /// integers, or `None` if it's missing, invalid or negative.
+    pub fn uint_attr(&self, attr: &str) -> Option<u32> {
+        self.int_attr(attr)
+            .and_then(|value| u32::try_from(value).ok())
+    }
+
+    /// Sets an integer attribute.
+    pub fn set_int_attr(&mut self, attr: &str, value: i32) {
+        self.set_attr(attr, &value.to_string());
+    }
+
+    /// Returns a URL attribute with surrounding ASCII whitespace stripped.
+    /// Resolving it against the document base URL is left to the caller.
+    pub fn url_attr(&self, attr: &str) -> Option<&str> {
+        self.attr(attr)
+            .map(|value| value.trim_matches(|c: char| c.is_ascii_whitespace()))
+    }
+
+    /// Returns the canonical keyword matching an enumerated attribute, ASCII
+    /// case-insensitively. A missing attribute gives `missing`, a value that
+    /// isn't one of `keywords` gives `invalid`.
+    pub fn enum_attr<'k>(
+        &self,
+        attr: &str,
+        keywords: &[&'k str],
+        missing: Option<&'k str>,
+        invalid: Option<&'k str>,
+    ) -> Option<&'k str> {
+        match self.attr(attr) {
+            None => missing,
+            Some(value) => keywords
+                .iter()
+                .find(|keyword| keyword.eq_ignore_ascii_case(value))
+                .copied()
+                .or(invalid),
+        }
+    }
+
+    /// Returns true if the `hidden` attribute is present.
+    pub fn hidden(&self) -> bool {
+        self.bool_attr("hidden")
+    }
+
+    /// Sets or removes the `hidden` attribute.
+    pub fn set_hidden(&mut self, hidden: bool) {
+        self.set_bool_attr("hidden", hidden)
+    }
+
+    /// Returns true if the `disabled` attribute is present.
+    pub fn disabled(&self) -> bool {
+        self.bool_attr("disabled")
+    }
+
+    /// Sets or removes the `disabled` attribute.
+    pub fn set_disabled(&mut self, disabled: bool) {
+        self.set_bool_attr("disabled", disabled)
+    }
+
+    /// Returns the reflected `tabindex`. Without a valid attribute, this is 0
+    /// for the elements the spec lists as focusable by default, like `a`,
+    /// `button` and `input`, and -1 otherwise. `summary` counts as
+    /// focusable whether or not it's the summary of its `details`, as the
+    /// element doesn't know its parent.
+    pub fn tab_index(&self) -> i32 {
+        self.int_attr("tabindex").unwrap_or_else(|| {
+            let focusable = match self.name.ns {
+                ns!(html) => matches!(
+                    self.name.local,
+                    local_name!("a")
+                        | local_name!("area")
+                        | local_name!("button")
+                        | local_name!("frame")
+                        | local_name!("iframe")
+                        | local_name!("input")
+                        | local_name!("object")
+                        | local_name!("select")
+                        | local_name!("textarea")
+                        | local_name!("summary")
+                ),
+                ns!(svg) => self.name.local == local_name!("a"),
+                _ => false,
+            };
+
+            if focusable {
+                0
+            } else {
+                -1
+            }
+        })
+    }
+
+    /// Sets the `tabindex` attribute.
+    pub fn set_tab_index(&mut self, value: i32) {
+        self.set_int_attr("tabindex", value)
+    }
+
+    /// Returns the reflected `colspan`, clamped to 1..=1000 and defaulting
+    /// to 1.
+    pub fn col_span(&self) -> u32 {
+        self.uint_attr("colspan")
+            .filter(|value| *value > 0)
+            .map_or(1, |value| value.min(1000))
+    }
+
+    /// Returns the reflected `rowspan`, clamped to 0..=65534 and defaulting
+    /// to 1.
+    pub fn row_span(&self) -> u32 {
+        self.uint_attr("rowspan")
+            .map_or(1, |value| value.min(65534))
+    }
+
+    /// Returns the reflected `type` of an `input`, defaulting to `text`.
+    pub fn input_type(&self) -> &'static str {
+        self.enum_attr("type", INPUT_TYPES, Some("text"), Some("text"))
+            .unwrap_or("text")
+    }
+}
+
+const INPUT_TYPES: &[&str] = &[
+    "hidden",
+    "text",
+    "search",
+    "tel",
+    "url",
+    "email",
+    "password",
+    "date",
+    "month",
+    "week",
+    "time",
+    "datetime-local",
+    "number",
+    "range",
+    "color",
+    "checkbox",
+    "radio",
+    "file",
+    "submit",
+    "image",
+    "reset",
+    "button",
+];
+
+/// The HTML rules for parsing integers: leading whitespace and trailing
+/// garbage are ignored, values that don't fit an `i32` are invalid.
+fn parse_integer(value: &str) -> Option<i32> {
+    let value = value.trim_start_matches(|c: char| c.is_ascii_whitespace());
+    let (negative, digits) = match value.as_bytes().first() {
+        Some(b'-') => (true, &value[1..]),
+        Some(b'+') => (false, &value[1..]),
+        _ => (false, value),
+    };
+
+    let len = digits.bytes().take_while(u8::is_ascii_digit).count();
+    if len == 0 {
+        return None;
+    }
+
+    let value: i64 = digits[..len].parse().ok()?;
+    i32::try_from(if negative { -value } else { value }).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_integer;
+    use crate::Document;
+
+    #[test]
+    fn integers_follow_the_html_rules() {
+        assert_eq!(parse_integer("  42px"), Some(42));
+        assert_eq!(parse_integer("-7"), Some(-7));
+        assert_eq!(parse_integer("+3"), Some(3));
+        assert_eq!(parse_integer("x1"), None);
+        assert_eq!(parse_integer("99999999999"), None);
+    }
+
+    #[test]
+    fn tab_index_defaults() {
+        let dom = Document::parse(
+            r#"<a>a</a><area><button></button><div></div><div tabindex="2"></div><svg><a></a></svg>"#,
+        );
+        let tab_indexes = dom
+            .select("a, area, button, div")
+            .iter()
+            .map(|&node| dom[node].as_element().unwrap().tab_index())
+            .collect::<Vec<_>>();
+        assert_eq!(tab_indexes, [0, 0, 0, -1, 2, 0]);
+    }
+
+    #[test]
+    fn reflected_attributes() {
+        let mut dom = Document::parse(r#"<input type="EMAIL"><input type="bogus">"#);
+
+        let inputs = dom.select("input");
+        let types = inputs
+            .iter()
+            .map(|&node| dom[node].as_element().unwrap().input_type())
+            .collect::<Vec<_>>();
+        assert_eq!(types, ["email", "text"]);
+
+        let input = inputs.get(0).unwrap();
+        let element = dom[input].as_element_mut().unwrap();
+        element.set_hidden(true);
+        element.set_disabled(true);
+        assert!(element.hidden() && element.disabled());
+        element.set_disabled(false);
+        assert!(element.attr("disabled").is_none());
+    }
+
+    #[test]
+    fn table_spans_are_clamped() {
+        let dom = Document::parse(
+            r#"<table><tr><td colspan="0" rowspan="70000"></td><td colspan="2000"></td></tr></table>"#,
+        );
+        let spans = dom
+            .select("td")
+            .iter()
+            .map(|&node| {
+                let element = dom[node].as_element().unwrap();
+                (element.col_span(), element.row_span())
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(spans, [(1, 65534), (1000, 1)]);
+    }
+}
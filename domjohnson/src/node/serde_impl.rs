@@ -0,0 +1,80 @@
+use std::borrow::Cow;
+
+use html5ever::{namespace_url, ns, Attribute, LocalName, Namespace, Prefix, QualName};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use super::Element;
+
+/// An element as `{"tag", "ns", "prefix", "attrs"}`, with namespaces kept
+/// for the element and each attribute. Attributes are in source order with
+/// the `deterministic` feature, in arbitrary order otherwise.
+#[derive(Serialize, Deserialize)]
+struct ElementRepr<'a> {
+    tag: Cow<'a, str>,
+    #[serde(default = "html_ns")]
+    ns: Cow<'a, str>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    prefix: Option<Cow<'a, str>>,
+    #[serde(default)]
+    attrs: Vec<AttrRepr<'a>>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct AttrRepr<'a> {
+    name: Cow<'a, str>,
+    value: Cow<'a, str>,
+    #[serde(default, skip_serializing_if = "str::is_empty")]
+    ns: Cow<'a, str>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    prefix: Option<Cow<'a, str>>,
+}
+
+fn html_ns() -> Cow<'static, str> {
+    Cow::Owned(ns!(html).to_string())
+}
+
+impl Serialize for Element {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        ElementRepr {
+            tag: Cow::Borrowed(&self.name.local),
+            ns: Cow::Borrowed(&self.name.ns),
+            prefix: self.name.prefix.as_deref().map(Cow::Borrowed),
+            attrs: self
+                .attrs
+                .iter()
+                .map(|(name, value)| AttrRepr {
+                    name: Cow::Borrowed(&name.local),
+                    value: Cow::Borrowed(value),
+                    ns: Cow::Borrowed(&name.ns),
+                    prefix: name.prefix.as_deref().map(Cow::Borrowed),
+                })
+                .collect(),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Element {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let repr = ElementRepr::deserialize(deserializer)?;
+        let name = qual_name(repr.prefix, repr.ns, repr.tag);
+        let attrs = repr
+            .attrs
+            .into_iter()
+            .map(|attr| Attribute {
+                name: qual_name(attr.prefix, attr.ns, attr.name),
+                value: attr.value.as_ref().into(),
+            })
+            .collect();
+
+        Ok(Element::new(name, attrs))
+    }
+}
+
+fn qual_name(prefix: Option<Cow<'_, str>>, ns: Cow<'_, str>, local: Cow<'_, str>) -> QualName {
+    QualName::new(
+        prefix.map(|p| Prefix::from(p.as_ref())),
+        Namespace::from(ns.as_ref()),
+        LocalName::from(local.as_ref()),
+    )
+}
@@ -0,0 +1,303 @@
+use cssparser::{
+    parse_important, AtRuleParser, CowRcStr, DeclarationListParser, DeclarationParser, Delimiter,
+    ParseError, Parser, ParserInput,
+};
+use std::{fmt, slice};
+
+use super::Element;
+
+/// A single declaration of an inline style.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Declaration {
+    /// The property name, lowercased unless it's a custom property.
+    pub name: String,
+
+    /// The property value as written, without `!important`.
+    pub value: String,
+
+    /// Whether the declaration is `!important`.
+    pub important: bool,
+}
+
+/// The declarations of a `style` attribute, in source order.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Style {
+    declarations: Vec<Declaration>,
+}
+
+impl Style {
+    /// Parses a declaration list, skipping invalid declarations. A property
+    /// declared more than once keeps only its winning declaration.
+    pub fn parse(css: &str) -> Style {
+        let mut input = ParserInput::new(css);
+        let mut parser = Parser::new(&mut input);
+        let mut style = Style::default();
+
+        for declaration in DeclarationListParser::new(&mut parser, StyleParser).flatten() {
+            if let Some(idx) = style.position(&declaration.name) {
+                if style.declarations[idx].important && !declaration.important {
+                    continue;
+                }
+                style.declarations.remove(idx);
+            }
+            style.declarations.push(declaration);
+        }
+
+        style
+    }
+
+    /// Returns the value of a property.
+    pub fn get_property(&self, name: &str) -> Option<&str> {
+        self.get(name).map(|d| d.value.as_str())
+    }
+
+    /// Returns true if the property is declared `!important`.
+    pub fn is_important(&self, name: &str) -> bool {
+        self.get(name).is_some_and(|d| d.important)
+    }
+
+    /// Sets a property, replacing an existing declaration in place. An empty
+    /// value removes the property. A trailing `!important` in `value` is the
+    /// same as passing `important`.
+    ///
+    /// Returns false, leaving the style unchanged, if `value` would end the
+    /// declaration, as a `;` outside of a string or block does.
+    pub fn set_property(&mut self, name: &str, value: &str, important: bool) -> bool {
+        if value.trim().is_empty() {
+            self.remove_property(name);
+            return true;
+        }
+
+        let mut input = ParserInput::new(value);
+        let mut parser = Parser::new(&mut input);
+        let parsed = parser.parse_until_before(Delimiter::Semicolon, |input| {
+            StyleParser.parse_value(name.into(), input)
+        });
+        let mut declaration = match parsed {
+            Ok(declaration) if parser.is_exhausted() => declaration,
+            _ => return false,
+        };
+        declaration.important |= important;
+
+        match self.position(name) {
+            Some(idx) => self.declarations[idx] = declaration,
+            None => self.declarations.push(declaration),
+        }
+        true
+    }
+
+    /// Removes a property, returning its value.
+    pub fn remove_property(&mut self, name: &str) -> Option<String> {
+        self.position(name)
+            .map(|idx| self.declarations.remove(idx).value)
+    }
+
+    /// Returns an iterator over the declarations.
+    pub fn iter(&self) -> slice::Iter<'_, Declaration> {
+        self.declarations.iter()
+    }
+
+    /// Returns the number of declarations.
+    pub fn len(&self) -> usize {
+        self.declarations.len()
+    }
+
+    /// Returns true if there are no declarations.
+    pub fn is_empty(&self) -> bool {
+        self.declarations.is_empty()
+    }
+
+    fn get(&self, name: &str) -> Option<&Declaration> {
+        self.position(name).map(|idx| &self.declarations[idx])
+    }
+
+    fn position(&self, name: &str) -> Option<usize> {
+        let name = property_name(name);
+        self.declarations.iter().position(|d| d.name == name)
+    }
+}
+
+impl fmt::Display for Style {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (idx, declaration) in self.declarations.iter().enumerate() {
+            if idx > 0 {
+                f.write_str(" ")?;
+            }
+            write!(f, "{}: {}", declaration.name, declaration.value)?;
+            if declaration.important {
+                f.write_str(" !important")?;
+            }
+            f.write_str(";")?;
+        }
+        Ok(())
+    }
+}
+
+/// An editable view over an element's `style` attribute. Every change is
+/// written back to the attribute.
+pub struct StyleMut<'a> {
+    element: &'a mut Element,
+    style: Style,
+}
+
+impl<'a> StyleMut<'a> {
+    pub(crate) fn new(element: &'a mut Element) -> Self {
+        let style = element.style();
+        StyleMut { element, style }
+    }
+
+    /// Returns the value of a property.
+    pub fn get_property(&self, name: &str) -> Option<&str> {
+        self.style.get_property(name)
+    }
+
+    /// Sets a property, see [`Style::set_property`].
+    pub fn set_property(&mut self, name: &str, value: &str, important: bool) -> bool {
+        if !self.style.set_property(name, value, important) {
+            return false;
+        }
+        self.element.set_style(&self.style);
+        true
+    }
+
+    /// Removes a property, returning its value.
+    pub fn remove_property(&mut self, name: &str) -> Option<String> {
+        let value = self.style.remove_property(name)?;
+        self.element.set_style(&self.style);
+        Some(value)
+    }
+
+    /// Returns the parsed style.
+    pub fn style(&self) -> &Style {
+        &self.style
+    }
+}
+
+/// Custom properties are case-sensitive, everything else isn't.
+fn property_name(name: &str) -> String {
+    if name.starts_with("--") {
+        name.to_string()
+    } else {
+        name.to_ascii_lowercase()
+    }
+}
+
+struct StyleParser;
+
+impl<'i> DeclarationParser<'i> for StyleParser {
+    type Declaration = Declaration;
+    type Error = ();
+
+    fn parse_value<'t>(
+        &mut self,
+        name: CowRcStr<'i>,
+        input: &mut Parser<'i, 't>,
+    ) -> Result<Self::Declaration, ParseError<'i, Self::Error>> {
+        let start = input.position();
+        let end;
+        let mut important = false;
+
+        loop {
+            let position = input.position();
+            let is_important = input
+                .try_parse(|input| {
+                    parse_important(input)?;
+                    input.expect_exhausted()
+                })
+                .is_ok();
+
+            if is_important {
+                important = true;
+                end = position;
+                break;
+            }
+
+            if input.next_including_whitespace_and_comments().is_err() {
+                end = input.position();
+                break;
+            }
+        }
+
+        let value = input.slice(start..end).trim();
+        if value.is_empty() {
+            return Err(input.new_custom_error(()));
+        }
+
+        Ok(Declaration {
+            name: property_name(&name),
+            value: value.to_string(),
+            important,
+        })
+    }
+}
+
+impl<'i> AtRuleParser<'i> for StyleParser {
+    type PreludeNoBlock = ();
+    type PreludeBlock = ();
+    type AtRule = Declaration;
+    type Error = ();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Style;
+    use crate::Document;
+
+    #[test]
+    fn parse_keeps_winning_declarations() {
+        let style = Style::parse("COLOR: red !important; color: blue; --Gap: 1px; bad; margin: 0");
+        assert_eq!(style.get_property("color"), Some("red"));
+        assert!(style.is_important("color"));
+        assert_eq!(style.get_property("--Gap"), Some("1px"));
+        assert_eq!(style.get_property("--gap"), None);
+        assert_eq!(
+            style.to_string(),
+            "color: red !important; --Gap: 1px; margin: 0;"
+        );
+    }
+
+    #[test]
+    fn set_property_rejects_values_ending_the_declaration() {
+        let mut style = Style::parse("color: red");
+        assert!(!style.set_property("color", "blue; background: url(x)", false));
+        assert!(!style.set_property("margin", "0;", false));
+        assert_eq!(style.to_string(), "color: red;");
+
+        assert!(style.set_property("content", "\";\"", false));
+        assert_eq!(style.get_property("content"), Some("\";\""));
+    }
+
+    #[test]
+    fn set_property_strips_important() {
+        let mut style = Style::default();
+        assert!(style.set_property("color", "red !important", false));
+        assert!(style.set_property("margin", "0 ! IMPORTANT", true));
+        assert_eq!(style.get_property("color"), Some("red"));
+        assert!(style.is_important("color"));
+        assert_eq!(
+            style.to_string(),
+            "color: red !important; margin: 0 !important;"
+        );
+    }
+
+    #[test]
+    fn style_mut_writes_back_to_the_attribute() {
+        let mut dom = Document::parse(r#"<p style="color: red; margin: 0">t</p>"#);
+        let p = dom.select("p").get(0).unwrap();
+
+        let element = dom[p].as_element_mut().unwrap();
+        let mut style = element.style_mut();
+        style.set_property("color", "blue", false);
+        style.set_property("padding", "1px", true);
+        assert_eq!(style.remove_property("margin").as_deref(), Some("0"));
+        assert_eq!(
+            element.attr("style").unwrap(),
+            "color: blue; padding: 1px !important;"
+        );
+
+        let mut style = element.style_mut();
+        style.remove_property("color");
+        style.set_property("padding", "", false);
+        assert!(element.attr("style").is_none());
+    }
+}
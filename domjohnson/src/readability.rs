@@ -0,0 +1,522 @@
+//! Main-content extraction in the style of Mozilla's Readability.
+//!
+//! Paragraph-like elements with enough text give points to their ancestors
+//! for their length and commas. The ancestors start with a score for their
+//! tag and for hints in their class and id, like `article` or `sidebar`,
+//! and the final scores are scaled down by link density, so that
+//! navigation and link lists lose out to prose. The document isn't
+//! modified: the article is returned as the id of the best element.
+
+use std::collections::HashMap;
+
+use generational_indextree::NodeId;
+use html5ever::{local_name, namespace_url, ns};
+
+use crate::element::node_ref::Edge;
+use crate::node::Element;
+use crate::{Document, NodeRef};
+
+/// The main content of a page and its metadata.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Article {
+    /// The element holding the main content.
+    pub content: NodeId,
+
+    /// The title, from the `og:title` meta tag, the `title` element without
+    /// the site name, or the only `h1`.
+    pub title: Option<String>,
+
+    /// The author, from the `author` meta tag or a byline element.
+    pub byline: Option<String>,
+
+    /// A summary, from the description meta tags or the first paragraph.
+    pub excerpt: Option<String>,
+
+    /// The URL of the lead image as written in the document, from the
+    /// `og:image` meta tag or the first image of the content.
+    pub lead_image: Option<String>,
+}
+
+/// Finds the main content of a document, or returns `None` if there's no
+/// paragraph long enough to go by.
+pub fn extract(dom: &Document) -> Option<Article> {
+    let root = dom.node_ref(dom.root());
+    let body = match dom.select("body").get(0) {
+        Some(body) => dom.node_ref(body),
+        None => root,
+    };
+
+    let content = top_candidate(body)?;
+
+    Some(Article {
+        content: content.id,
+        title: title(root),
+        byline: byline(root),
+        excerpt: meta(
+            root,
+            &["description", "og:description", "twitter:description"],
+        )
+        .or_else(|| {
+            elements(content)
+                .filter(|(_, e)| e.name.local == local_name!("p"))
+                .find_map(|(node, _)| clean(&node.inner_text()))
+        }),
+        lead_image: meta(root, &["og:image", "twitter:image"]).or_else(|| {
+            elements(content)
+                .filter(|(_, e)| e.name.local == local_name!("img"))
+                .find_map(|(_, e)| e.attr("src").and_then(|src| clean(src)))
+        }),
+    })
+}
+
+/// Returns the highest scoring element.
+fn top_candidate(body: NodeRef<'_>) -> Option<NodeRef<'_>> {
+    let mut paragraphs = Vec::new();
+    collect_paragraphs(body, &mut paragraphs);
+
+    let mut scores: HashMap<NodeId, (NodeRef<'_>, f64)> = HashMap::new();
+    for paragraph in paragraphs {
+        let text = paragraph.inner_text();
+        let len = text.trim().chars().count();
+        if len < 25 {
+            continue;
+        }
+
+        let score = 1.0 + text.matches(',').count() as f64 + (len as f64 / 100.0).min(3.0);
+
+        let ancestors = std::iter::successors(paragraph.parent(), |node| node.parent());
+        for (level, ancestor) in ancestors.take(5).enumerate() {
+            let Some(element) = ancestor.node().as_element() else {
+                break;
+            };
+            let divider = match level {
+                0 => 1.0,
+                1 => 2.0,
+                _ => level as f64 * 3.0,
+            };
+            scores
+                .entry(ancestor.id)
+                .or_insert_with(|| (ancestor, initial_score(element)))
+                .1 += score / divider;
+        }
+    }
+
+    // Candidates are compared in document order, so that the first one
+    // wins a tie whatever the hash map order.
+    let root = std::iter::successors(Some(body), |node| node.parent())
+        .last()
+        .unwrap_or(body);
+    let (mut top, _) = root
+        .traverse()
+        .filter_map(|edge| match edge {
+            Edge::Open(node) => scores.remove(&node.id),
+            Edge::Close(_) => None,
+        })
+        .map(|(node, score)| (node, score * (1.0 - link_density(node))))
+        .reduce(|top, candidate| if candidate.1 > top.1 { candidate } else { top })?;
+
+    // Prefer the outermost of nested wrappers that hold nothing else.
+    while let Some(parent) = top.parent().filter(|p| *p != body && p.node().is_element()) {
+        if elements_children(parent).count() != 1 {
+            break;
+        }
+        top = parent;
+    }
+
+    Some(top)
+}
+
+/// Collects the elements that are scored, skipping hidden, unlikely and
+/// non-content subtrees.
+fn collect_paragraphs<'a>(node: NodeRef<'a>, out: &mut Vec<NodeRef<'a>>) {
+    for child in node.children() {
+        let Some(element) = child.node().as_element() else {
+            continue;
+        };
+
+        if element.name.ns != ns!(html) || element.hidden() || is_unlikely(element) {
+            continue;
+        }
+
+        match element.name.local {
+            local_name!("script")
+            | local_name!("style")
+            | local_name!("noscript")
+            | local_name!("template")
+            | local_name!("nav")
+            | local_name!("aside")
+            | local_name!("footer")
+            | local_name!("form")
+            | local_name!("iframe")
+            | local_name!("button") => {}
+            local_name!("p") | local_name!("pre") | local_name!("td") => out.push(child),
+            // Divs of text are written as paragraphs.
+            local_name!("div") if !has_block_content(child) => out.push(child),
+            _ => collect_paragraphs(child, out),
+        }
+    }
+}
+
+fn initial_score(element: &Element) -> f64 {
+    let score = match element.name.local {
+        local_name!("div") | local_name!("article") => 5.0,
+        local_name!("pre") | local_name!("td") | local_name!("blockquote") => 3.0,
+        local_name!("address")
+        | local_name!("ol")
+        | local_name!("ul")
+        | local_name!("dl")
+        | local_name!("dd")
+        | local_name!("dt")
+        | local_name!("li")
+        | local_name!("form") => -3.0,
+        local_name!("h1")
+        | local_name!("h2")
+        | local_name!("h3")
+        | local_name!("h4")
+        | local_name!("h5")
+        | local_name!("h6")
+        | local_name!("th") => -5.0,
+        _ => 0.0,
+    };
+    score + class_weight(element)
+}
+
+const POSITIVE: &[&str] = &[
+    "article", "blog", "body", "content", "entry", "hentry", "h-entry", "main", "page", "post",
+    "story", "text",
+];
+
+const NEGATIVE: &[&str] = &[
+    "-ad-",
+    "banner",
+    "combx",
+    "comment",
+    "com-",
+    "contact",
+    "foot",
+    "gdpr",
+    "hidden",
+    "masthead",
+    "media",
+    "meta",
+    "outbrain",
+    "promo",
+    "related",
+    "scroll",
+    "share",
+    "shopping",
+    "shoutbox",
+    "sidebar",
+    "skyscraper",
+    "sponsor",
+    "tags",
+    "tool",
+    "widget",
+];
+
+const UNLIKELY: &[&str] = &[
+    "-ad-",
+    "ad-break",
+    "agegate",
+    "banner",
+    "breadcrumbs",
+    "combx",
+    "comment",
+    "community",
+    "cover-wrap",
+    "disqus",
+    "extra",
+    "footer",
+    "gdpr",
+    "header",
+    "legends",
+    "menu",
+    "pager",
+    "pagination",
+    "popup",
+    "related",
+    "remark",
+    "replies",
+    "rss",
+    "shoutbox",
+    "sidebar",
+    "skyscraper",
+    "social",
+    "sponsor",
+    "supplemental",
+    "yom-remote",
+];
+
+const MAYBE: &[&str] = &[
+    "and", "article", "body", "column", "content", "main", "shadow",
+];
+
+const UNLIKELY_ROLES: &[&str] = &[
+    "alert",
+    "alertdialog",
+    "complementary",
+    "dialog",
+    "menu",
+    "menubar",
+    "navigation",
+];
+
+/// Scores the class and id separately, each by whether it has a positive
+/// or negative hint.
+fn class_weight(element: &Element) -> f64 {
+    [element.attr("class"), element.attr("id")]
+        .into_iter()
+        .flatten()
+        .map(|hints| {
+            let hints = hints.to_ascii_lowercase();
+            let mut weight = 0.0;
+            if contains_any(&hints, NEGATIVE) {
+                weight -= 25.0;
+            }
+            if contains_any(&hints, POSITIVE) {
+                weight += 25.0;
+            }
+            weight
+        })
+        .sum()
+}
+
+fn is_unlikely(element: &Element) -> bool {
+    if element
+        .attr("role")
+        .is_some_and(|role| UNLIKELY_ROLES.contains(&role.trim()))
+    {
+        return true;
+    }
+
+    if matches!(
+        element.name.local,
+        local_name!("a") | local_name!("article") | local_name!("main") | local_name!("body")
+    ) {
+        return false;
+    }
+
+    let hints = hints(element);
+    contains_any(&hints, UNLIKELY) && !contains_any(&hints, MAYBE)
+}
+
+/// Returns the class and id, lowercased.
+fn hints(element: &Element) -> String {
+    let class = element.attr("class").map_or("", |c| c.as_str());
+    let id = element.id().unwrap_or("");
+    format!("{} {}", class, id).to_ascii_lowercase()
+}
+
+fn contains_any(hints: &str, words: &[&str]) -> bool {
+    words.iter().any(|word| hints.contains(word))
+}
+
+fn has_block_content(node: NodeRef<'_>) -> bool {
+    elements(node).skip(1).any(|(_, e)| {
+        matches!(
+            e.name.local,
+            local_name!("blockquote")
+                | local_name!("div")
+                | local_name!("dl")
+                | local_name!("img")
+                | local_name!("ol")
+                | local_name!("p")
+                | local_name!("pre")
+                | local_name!("table")
+                | local_name!("ul")
+        )
+    })
+}
+
+/// Returns the share of the text that is inside links.
+fn link_density(node: NodeRef<'_>) -> f64 {
+    let total = text_len(node);
+    if total == 0 {
+        return 0.0;
+    }
+
+    let mut links = 0;
+    let mut depth = 0;
+    for edge in node.traverse() {
+        match edge {
+            Edge::Open(n) if is_link(&n) => depth += 1,
+            Edge::Close(n) if is_link(&n) => depth -= 1,
+            Edge::Open(n) if depth > 0 => {
+                links += n.node().as_text().map_or(0, |t| t.trim().chars().count())
+            }
+            _ => {}
+        }
+    }
+
+    links as f64 / total as f64
+}
+
+fn is_link(node: &NodeRef<'_>) -> bool {
+    node.node()
+        .as_element()
+        .is_some_and(|e| e.name.local == local_name!("a"))
+}
+
+fn text_len(node: NodeRef<'_>) -> usize {
+    node.text().map(|text| text.trim().chars().count()).sum()
+}
+
+fn title(root: NodeRef<'_>) -> Option<String> {
+    if let Some(title) = meta(root, &["og:title", "twitter:title"]) {
+        return Some(title);
+    }
+
+    let title = elements(root)
+        .find(|(_, e)| e.name.local == local_name!("title"))
+        .and_then(|(node, _)| clean(&node.text().map(|t| t.as_str()).collect::<String>()));
+
+    if let Some(title) = title {
+        // Drop the site name from "Headline | Site".
+        let cut = [" | ", " - ", " – ", " — ", " » ", " :: "]
+            .iter()
+            .filter_map(|sep| title.rfind(sep))
+            .max();
+        if let Some(cut) = cut {
+            if title[..cut].split_whitespace().count() >= 3 {
+                return Some(title[..cut].to_string());
+            }
+        }
+        return Some(title);
+    }
+
+    let mut headings = elements(root).filter(|(_, e)| e.name.local == local_name!("h1"));
+    match (headings.next(), headings.next()) {
+        (Some((h1, _)), None) => clean(&h1.inner_text()),
+        _ => None,
+    }
+}
+
+fn byline(root: NodeRef<'_>) -> Option<String> {
+    if let Some(author) = meta(root, &["author", "article:author", "dc.creator"]) {
+        // `article:author` is often a profile URL.
+        if !author.starts_with("http") {
+            return Some(author);
+        }
+    }
+
+    elements(root)
+        .filter(|(_, e)| {
+            e.attr("rel").is_some_and(|rel| rel == "author")
+                || e.attr("itemprop").is_some_and(|p| p.contains("author"))
+                || contains_any(&hints(e), &["byline", "author", "dateline", "writtenby"])
+        })
+        .filter_map(|(node, _)| clean(&node.inner_text()))
+        .find(|text| text.chars().count() < 100)
+}
+
+/// Returns the content of the first meta tag with one of the names or
+/// properties, in order of preference.
+fn meta(root: NodeRef<'_>, keys: &[&str]) -> Option<String> {
+    let metas = elements(root)
+        .filter(|(_, e)| e.name.local == local_name!("meta"))
+        .filter_map(|(_, e)| {
+            let key = e.attr("property").or_else(|| e.attr("name"))?;
+            let content = clean(e.attr("content")?)?;
+            Some((key.to_ascii_lowercase(), content))
+        })
+        .collect::<Vec<_>>();
+
+    keys.iter().find_map(|key| {
+        metas
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, content)| content.clone())
+    })
+}
+
+/// Returns the HTML elements of a subtree, the node included, in document
+/// order.
+fn elements(node: NodeRef<'_>) -> impl Iterator<Item = (NodeRef<'_>, &Element)> {
+    node.traverse().filter_map(|edge| match edge {
+        Edge::Open(node) => node
+            .node()
+            .as_element()
+            .filter(|e| e.name.ns == ns!(html))
+            .map(|e| (node, e)),
+        Edge::Close(_) => None,
+    })
+}
+
+fn elements_children(node: NodeRef<'_>) -> impl Iterator<Item = NodeRef<'_>> {
+    node.children().filter(|child| child.node().is_element())
+}
+
+/// Collapses whitespace, returning `None` for blank text.
+fn clean(text: &str) -> Option<String> {
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    (!text.is_empty()).then_some(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::extract;
+    use crate::Document;
+
+    const P: &str =
+        "<p>This is a long paragraph of prose, with commas, and enough text to count.</p>";
+
+    fn content_id(html: &str) -> Option<String> {
+        let dom = Document::parse(html);
+        let article = extract(&dom)?;
+        dom.node_ref(article.content)
+            .as_element()
+            .and_then(|e| e.attr("id").map(|id| id.to_string()))
+    }
+
+    #[test]
+    fn ties_go_to_the_first_candidate() {
+        let html = format!("<div id=a>{P}{P}</div><div id=b>{P}{P}</div>");
+        for _ in 0..10 {
+            assert_eq!(content_id(&html).as_deref(), Some("a"));
+        }
+    }
+
+    #[test]
+    fn prose_beats_links_and_sidebars() {
+        let links = format!(
+            "<div id=links>{}</div>",
+            P.replace("<p>", "<p><a href=/>")
+                .replace("</p>", "</a></p>")
+        );
+        let html = format!(
+            "{links}<div id=sidebar class=sidebar>{P}{P}</div><div id=story>{P}{P}{P}</div>"
+        );
+        assert_eq!(content_id(&html).as_deref(), Some("story"));
+    }
+
+    #[test]
+    fn nested_wrappers_are_included() {
+        let html = format!("<div id=outer><div id=inner>{P}{P}</div></div><p>x</p>");
+        assert_eq!(content_id(&html).as_deref(), Some("outer"));
+    }
+
+    #[test]
+    fn metadata() {
+        let html = format!(
+            "<head><title>A much longer headline here | Site</title>\
+             <meta name=author content='Jo Doe'></head>\
+             <body><div id=a>{P}<img src=lead.png>{P}</div></body>"
+        );
+        let article = extract(&Document::parse(&html)).unwrap();
+        assert_eq!(
+            article.title.as_deref(),
+            Some("A much longer headline here")
+        );
+        assert_eq!(article.byline.as_deref(), Some("Jo Doe"));
+        assert_eq!(
+            article.excerpt.as_deref(),
+            Some("This is a long paragraph of prose, with commas, and enough text to count.")
+        );
+        assert_eq!(article.lead_image.as_deref(), Some("lead.png"));
+    }
+
+    #[test]
+    fn short_pages_have_no_article() {
+        assert!(extract(&Document::parse("<p>short</p>")).is_none());
+    }
+}
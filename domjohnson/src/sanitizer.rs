@@ -0,0 +1,492 @@
+use std::collections::{HashMap, HashSet};
+
+use generational_indextree::NodeId;
+use html5ever::{local_name, namespace_url, ns, LocalName, QualName};
+
+use crate::node::Node;
+use crate::Document;
+
+/// Cleans untrusted HTML in place by removing everything that isn't on its
+/// allowlists of tags, attributes per tag and URL schemes.
+///
+/// Some things are removed whatever the allowlists say: `on*` event handler
+/// attributes, `javascript:` and `vbscript:` URLs, namespaced attributes
+/// and elements outside the HTML namespace, like SVG and MathML. `script`,
+/// `style` and other elements whose contents aren't plain markup are always
+/// removed with their contents, even when [`Sanitizer::keep_content`] is
+/// set.
+///
+/// `html`, `head` and `body` are kept so that the document stays well
+/// formed, but their attributes are still checked.
+#[derive(Debug, Clone)]
+pub struct Sanitizer {
+    tags: HashSet<LocalName>,
+    attrs: HashMap<LocalName, HashSet<LocalName>>,
+    generic_attrs: HashSet<LocalName>,
+    url_schemes: HashSet<String>,
+    keep_content: bool,
+    allow_comments: bool,
+}
+
+/// What [`Sanitizer::sanitize`] removed, in document order. Nodes inside a
+/// removed subtree aren't reported separately.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SanitizeReport {
+    pub removed: Vec<Removal>,
+}
+
+/// Something removed by a [`Sanitizer`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Removal {
+    /// An element, with its contents unless `kept_content` is true.
+    Element {
+        name: String,
+        kept_content: bool,
+    },
+    /// An attribute of an element that was kept.
+    Attribute {
+        element: String,
+        name: String,
+        value: String,
+    },
+    Comment,
+    ProcessingInstruction,
+}
+
+impl SanitizeReport {
+    /// Returns true if nothing was removed.
+    pub fn is_clean(&self) -> bool {
+        self.removed.is_empty()
+    }
+}
+
+impl Default for Sanitizer {
+    fn default() -> Self {
+        Sanitizer::basic()
+    }
+}
+
+impl Sanitizer {
+    /// Creates a sanitizer that allows nothing, to be filled in with the
+    /// `allow_*` methods.
+    pub fn new() -> Self {
+        Sanitizer {
+            tags: HashSet::new(),
+            attrs: HashMap::new(),
+            generic_attrs: HashSet::new(),
+            url_schemes: HashSet::new(),
+            keep_content: true,
+            allow_comments: false,
+        }
+    }
+
+    /// Allows text formatting only: bold, italics, code, sub- and
+    /// superscripts and line breaks, without any attributes.
+    pub fn strict() -> Self {
+        Sanitizer::new().allow_tags(&[
+            "b", "br", "code", "em", "i", "s", "strong", "sub", "sup", "u",
+        ])
+    }
+
+    /// Allows the markup of comments and forum posts: text formatting,
+    /// paragraphs, lists, quotes, preformatted text and `http`, `https` and
+    /// `mailto` links.
+    pub fn basic() -> Self {
+        Sanitizer::strict()
+            .allow_tags(&[
+                "a",
+                "abbr",
+                "blockquote",
+                "cite",
+                "dd",
+                "del",
+                "dl",
+                "dt",
+                "ins",
+                "kbd",
+                "li",
+                "mark",
+                "ol",
+                "p",
+                "pre",
+                "q",
+                "small",
+                "span",
+                "ul",
+            ])
+            .allow_attrs("a", &["href", "title"])
+            .allow_attrs("abbr", &["title"])
+            .allow_attrs("blockquote", &["cite"])
+            .allow_attrs("q", &["cite"])
+            .allow_attrs("ol", &["start", "reversed"])
+            .allow_url_schemes(&["http", "https", "mailto"])
+    }
+
+    /// Allows the markup of articles: everything in [`Sanitizer::basic`]
+    /// plus headings, images, tables, sections and figures, with `class`,
+    /// `title`, `lang` and `dir` on any element.
+    pub fn relaxed() -> Self {
+        Sanitizer::basic()
+            .allow_tags(&[
+                "article",
+                "aside",
+                "bdi",
+                "bdo",
+                "caption",
+                "col",
+                "colgroup",
+                "details",
+                "dfn",
+                "div",
+                "figcaption",
+                "figure",
+                "footer",
+                "h1",
+                "h2",
+                "h3",
+                "h4",
+                "h5",
+                "h6",
+                "header",
+                "hr",
+                "img",
+                "rp",
+                "rt",
+                "ruby",
+                "samp",
+                "section",
+                "summary",
+                "table",
+                "tbody",
+                "td",
+                "tfoot",
+                "th",
+                "thead",
+                "time",
+                "tr",
+                "var",
+                "wbr",
+            ])
+            .allow_generic_attrs(&["class", "dir", "lang", "title"])
+            .allow_attrs("a", &["hreflang"])
+            .allow_attrs("img", &["src", "alt", "width", "height"])
+            .allow_attrs("col", &["span"])
+            .allow_attrs("colgroup", &["span"])
+            .allow_attrs("td", &["colspan", "rowspan", "headers", "align"])
+            .allow_attrs("th", &["colspan", "rowspan", "headers", "align", "scope"])
+            .allow_attrs("del", &["cite", "datetime"])
+            .allow_attrs("ins", &["cite", "datetime"])
+            .allow_attrs("time", &["datetime"])
+            .allow_attrs("details", &["open"])
+            .allow_attrs("ol", &["type"])
+            .allow_url_schemes(&["tel"])
+    }
+
+    /// Allows elements.
+    pub fn allow_tags(mut self, tags: &[&str]) -> Self {
+        self.tags
+            .extend(tags.iter().map(|&tag| LocalName::from(tag)));
+        self
+    }
+
+    /// Allows attributes on an element.
+    pub fn allow_attrs(mut self, tag: &str, attrs: &[&str]) -> Self {
+        self.attrs
+            .entry(LocalName::from(tag))
+            .or_default()
+            .extend(attrs.iter().map(|&attr| LocalName::from(attr)));
+        self
+    }
+
+    /// Allows attributes on any allowed element.
+    pub fn allow_generic_attrs(mut self, attrs: &[&str]) -> Self {
+        self.generic_attrs
+            .extend(attrs.iter().map(|&attr| LocalName::from(attr)));
+        self
+    }
+
+    /// Allows URL schemes, like `https`, in URL attributes such as `href`
+    /// and `src`. Relative URLs are always allowed.
+    pub fn allow_url_schemes(mut self, schemes: &[&str]) -> Self {
+        self.url_schemes
+            .extend(schemes.iter().map(|scheme| scheme.to_ascii_lowercase()));
+        self
+    }
+
+    /// Whether the contents of removed elements are kept in their place,
+    /// which is the default. Disable it to remove them too.
+    pub fn keep_content(mut self, keep: bool) -> Self {
+        self.keep_content = keep;
+        self
+    }
+
+    /// Whether comments are kept. They're removed by default.
+    pub fn allow_comments(mut self, allow: bool) -> Self {
+        self.allow_comments = allow;
+        self
+    }
+
+    /// Sanitizes the whole document. Removed nodes are freed, so they don't
+    /// linger in the arena or in [`Document::to_snapshot`].
+    pub fn sanitize(&self, dom: &mut Document) -> SanitizeReport {
+        let root = dom.root();
+        self.sanitize_children(dom, root)
+    }
+
+    /// Sanitizes the descendants of a node, leaving the node itself alone.
+    pub fn sanitize_children(&self, dom: &mut Document, node: NodeId) -> SanitizeReport {
+        let mut report = SanitizeReport::default();
+        self.children(dom, node, &mut report);
+        report
+    }
+
+    fn children(&self, dom: &mut Document, node: NodeId, report: &mut SanitizeReport) {
+        let children = dom.children(node).collect::<Vec<_>>();
+        for child in children {
+            self.node(dom, child, report);
+        }
+    }
+
+    fn node(&self, dom: &mut Document, node: NodeId, report: &mut SanitizeReport) {
+        let name = match &dom[node] {
+            Node::Document | Node::Fragment => return self.children(dom, node, report),
+            Node::Doctype(_) | Node::Text(_) => return,
+            Node::Comment(_) => {
+                if !self.allow_comments {
+                    report.removed.push(Removal::Comment);
+                    dom.free_subtree(node);
+                }
+                return;
+            }
+            Node::ProcessingInstruction(_) => {
+                report.removed.push(Removal::ProcessingInstruction);
+                dom.free_subtree(node);
+                return;
+            }
+            Node::Element(element) => element.name.clone(),
+        };
+
+        if self.allows_element(&name) {
+            self.attributes(dom, node, report);
+            return self.children(dom, node, report);
+        }
+
+        let keep_content = self.keep_content && !drops_content(&name);
+        report.removed.push(Removal::Element {
+            name: name.local.to_string(),
+            kept_content: keep_content,
+        });
+
+        if keep_content {
+            self.children(dom, node, report);
+            // The children take the element's place.
+            dom.delete(node);
+        } else {
+            dom.free_subtree(node);
+        }
+    }
+
+    fn allows_element(&self, name: &QualName) -> bool {
+        name.ns == ns!(html)
+            && (self.tags.contains(&name.local)
+                || matches!(
+                    name.local,
+                    local_name!("html") | local_name!("head") | local_name!("body")
+                ))
+    }
+
+    fn attributes(&self, dom: &mut Document, node: NodeId, report: &mut SanitizeReport) {
+        let Some(element) = dom[node].as_element_mut() else {
+            return;
+        };
+
+        let tag = element.name.local.clone();
+        let removed = element
+            .attrs
+            .iter()
+            .filter(|(name, value)| !self.allows_attr(&tag, name, value))
+            .map(|(name, value)| (name.clone(), value.clone()))
+            .collect::<Vec<_>>();
+
+        for (name, value) in removed {
+            element.remove_attr_ns(&name);
+            report.removed.push(Removal::Attribute {
+                element: tag.to_string(),
+                name: name.local.to_string(),
+                value,
+            });
+        }
+    }
+
+    fn allows_attr(&self, tag: &LocalName, name: &QualName, value: &str) -> bool {
+        if name.ns != ns!() || name.local.starts_with("on") {
+            return false;
+        }
+
+        let listed = self.generic_attrs.contains(&name.local)
+            || self
+                .attrs
+                .get(tag)
+                .is_some_and(|attrs| attrs.contains(&name.local));
+        if !listed {
+            return false;
+        }
+
+        match name.local {
+            local_name!("srcset") => value
+                .split(',')
+                .filter_map(|candidate| candidate.split_ascii_whitespace().next())
+                .all(|url| self.allows_url(url)),
+            _ if is_url_attr(&name.local) => self.allows_url(value),
+            _ => true,
+        }
+    }
+
+    /// Checks the scheme of a URL, after removing the whitespace and
+    /// control characters that browsers ignore, so that `java\tscript:`
+    /// is caught too.
+    fn allows_url(&self, url: &str) -> bool {
+        let url = url
+            .chars()
+            .filter(|c| !c.is_ascii_whitespace() && !c.is_control())
+            .collect::<String>();
+
+        let scheme = match url.find([':', '/', '?', '#']) {
+            Some(idx) if url[idx..].starts_with(':') => url[..idx].to_ascii_lowercase(),
+            // No scheme, so the URL is relative.
+            _ => return true,
+        };
+
+        !matches!(scheme.as_str(), "javascript" | "vbscript") && self.url_schemes.contains(&scheme)
+    }
+}
+
+/// Returns true for elements whose contents are code, or text that isn't
+/// meant to be shown, so are never kept.
+fn drops_content(name: &QualName) -> bool {
+    name.ns == ns!(html)
+        && matches!(
+            name.local,
+            local_name!("script")
+                | local_name!("style")
+                | local_name!("template")
+                | local_name!("title")
+                | local_name!("iframe")
+                | local_name!("frame")
+                | local_name!("frameset")
+                | local_name!("object")
+                | local_name!("embed")
+                | local_name!("applet")
+                | local_name!("noscript")
+                | local_name!("noembed")
+                | local_name!("noframes")
+                | local_name!("xmp")
+                | local_name!("plaintext")
+        )
+}
+
+fn is_url_attr(name: &LocalName) -> bool {
+    matches!(
+        *name,
+        local_name!("href")
+            | local_name!("src")
+            | local_name!("cite")
+            | local_name!("action")
+            | local_name!("formaction")
+            | local_name!("poster")
+            | local_name!("background")
+            | local_name!("longdesc")
+            | local_name!("data")
+            | local_name!("codebase")
+            | local_name!("manifest")
+            | local_name!("usemap")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Removal, Sanitizer};
+    use crate::Document;
+
+    fn sanitize(sanitizer: &Sanitizer, html: &str) -> String {
+        let mut dom = Document::parse(html);
+        sanitizer.sanitize(&mut dom);
+        let body = dom.select("body").get(0).unwrap();
+        dom.inner_html(body)
+    }
+
+    const HTML: &str = "<h1 onclick=x()>Head</h1><p>Text <b>bold</b> \
+                        <a href='javascript:alert(1)'>js</a> <a href=https://x.org>ok</a>\
+                        <img src=a.png></p><div><ul><li>one</li></ul></div>\
+                        <table><tr><td colspan=2>c</td></tr></table>";
+
+    #[test]
+    fn strict_keeps_text_formatting() {
+        assert_eq!(
+            sanitize(&Sanitizer::strict(), HTML),
+            "HeadText <b>bold</b> js okonec"
+        );
+    }
+
+    #[test]
+    fn basic_keeps_paragraphs_lists_and_links() {
+        assert_eq!(
+            sanitize(&Sanitizer::basic(), HTML),
+            "Head<p>Text <b>bold</b> <a>js</a> <a href=\"https://x.org\">ok</a></p>\
+             <ul><li>one</li></ul>c"
+        );
+    }
+
+    #[test]
+    fn relaxed_keeps_articles() {
+        assert_eq!(
+            sanitize(&Sanitizer::relaxed(), HTML),
+            "<h1>Head</h1><p>Text <b>bold</b> <a>js</a> <a href=\"https://x.org\">ok</a>\
+             <img src=\"a.png\"></p><div><ul><li>one</li></ul></div>\
+             <table><tbody><tr><td colspan=\"2\">c</td></tr></tbody></table>"
+        );
+    }
+
+    #[test]
+    fn report_lists_removals_in_order() {
+        let mut dom =
+            Document::parse("<p onclick=x()>a<!--c--></p><script>x()</script><svg><circle/></svg>");
+        let report = Sanitizer::basic().sanitize(&mut dom);
+
+        assert_eq!(
+            report.removed,
+            [
+                Removal::Attribute {
+                    element: "p".to_string(),
+                    name: "onclick".to_string(),
+                    value: "x()".to_string(),
+                },
+                Removal::Comment,
+                Removal::Element {
+                    name: "script".to_string(),
+                    kept_content: false,
+                },
+                Removal::Element {
+                    name: "svg".to_string(),
+                    kept_content: true,
+                },
+                Removal::Element {
+                    name: "circle".to_string(),
+                    kept_content: true,
+                },
+            ]
+        );
+        assert!(Sanitizer::basic().sanitize(&mut dom).is_clean());
+    }
+
+    #[test]
+    fn removed_nodes_are_freed() {
+        let mut dom =
+            Document::parse("<p>a</p><script>SECRET</script><div><b>SECRET</b></div><!--SECRET-->");
+        Sanitizer::strict().keep_content(false).sanitize(&mut dom);
+
+        assert_eq!(dom.orhpans().count(), 0);
+        let snapshot = dom.to_snapshot();
+        assert!(!snapshot.windows(6).any(|bytes| bytes == b"SECRET"));
+    }
+}
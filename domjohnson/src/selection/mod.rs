@@ -3,6 +3,8 @@ use crate::{
     Document,
 };
 use generational_indextree::NodeId;
+use html5ever::serialize::TraversalScope;
+use std::io::{self, Write};
 
 #[derive(Debug, Clone)]
 pub struct Selection {
@@ -43,6 +45,19 @@ impl Selection {
     pub fn iter(&self) -> core::slice::Iter<'_, NodeId> {
         self.nodes.iter()
     }
+
+    /// Writes the HTML of every selected node in order.
+    pub fn write_html<W: Write>(
+        &self,
+        dom: &Document,
+        writer: &mut W,
+        scope: TraversalScope,
+    ) -> io::Result<()> {
+        for &node in &self.nodes {
+            dom.node_ref(node).write_html(writer, scope.clone())?;
+        }
+        Ok(())
+    }
 }
 
 impl IntoIterator for Selection {
@@ -72,3 +87,47 @@ impl From<Vec<NodeId>> for Selection {
         Selection { nodes: value }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use html5ever::serialize::TraversalScope;
+
+    use crate::Document;
+
+    #[test]
+    fn write_html_writes_every_node() {
+        let dom = Document::parse("<p>a</p><div>b</div><p>c &amp; d</p>");
+
+        let mut buf = Vec::new();
+        dom.select("p")
+            .write_html(&dom, &mut buf, TraversalScope::IncludeNode)
+            .unwrap();
+        assert_eq!(buf, b"<p>a</p><p>c &amp; d</p>");
+
+        let mut buf = Vec::new();
+        dom.select("p")
+            .write_html(&dom, &mut buf, TraversalScope::ChildrenOnly(None))
+            .unwrap();
+        assert_eq!(buf, b"ac &amp; d");
+    }
+
+    #[test]
+    fn write_html_matches_outer_and_inner_html() {
+        let dom = Document::parse("<div><p>a<br>b</p><!--c--></div>");
+        let div = dom.select("div").get(0).unwrap();
+        let node = dom.node_ref(div);
+
+        let mut buf = Vec::new();
+        node.write_html(&mut buf, TraversalScope::IncludeNode)
+            .unwrap();
+        assert_eq!(String::from_utf8(buf).unwrap(), node.html());
+
+        let mut buf = Vec::new();
+        dom.write_html(&mut buf, TraversalScope::ChildrenOnly(None))
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(buf).unwrap(),
+            "<html><head></head><body><div><p>a<br>b</p><!--c--></div></body></html>"
+        );
+    }
+}